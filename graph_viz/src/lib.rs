@@ -91,6 +91,16 @@ impl GraphVizNode {
     }
 }
 
+/// Drops the children without recursion, so trees of deeply nested input don't overflow the stack.
+impl Drop for GraphVizNode {
+    fn drop(&mut self) {
+        let mut nodes = std::mem::take(&mut self.children);
+        while let Some(mut node) = nodes.pop() {
+            nodes.append(&mut node.children);
+        }
+    }
+}
+
 pub fn generate_dot_format(syntax_tree: &GraphVizNode) -> String {
     syntax_tree.to_dot_language()
 }
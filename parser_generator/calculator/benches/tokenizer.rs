@@ -0,0 +1,43 @@
+//! Throughput of the generated tokenizer on growing inputs, run with `cargo bench`.
+//!
+//! Every token is found in one anchored pass, so the time per megabyte stays
+//! the same while the input grows.
+
+use std::time::{Duration, Instant};
+
+include!(concat!(env!("OUT_DIR"), "/calculator.rs"));
+
+const MEGABYTE: usize = 1 << 20;
+
+fn expression(size: usize) -> String {
+    let mut input = String::with_capacity(size + 32);
+    let mut i = 1;
+    while input.len() < size {
+        input.push_str(&format!("({i} + 23) * 456 - {i} / 7 $ 2\n"));
+        i += 1;
+    }
+    input
+}
+
+fn measure(input: &str) -> (usize, Duration) {
+    let start = Instant::now();
+    let tokens = calculator::Tokenizer::tokenize(input.to_string()).unwrap();
+    (tokens.len(), start.elapsed())
+}
+
+fn main() {
+    // Warms up lazily compiled regexes.
+    measure(&expression(MEGABYTE / 16));
+
+    println!("{:>6} {:>10} {:>10} {:>10}", "MB", "tokens", "ms", "MB/s");
+    for megabytes in [1, 2, 4, 8] {
+        let input = expression(megabytes * MEGABYTE);
+        let (tokens, elapsed) = measure(&input);
+        let seconds = elapsed.as_secs_f64();
+        println!(
+            "{megabytes:>6} {tokens:>10} {:>10.1} {:>10.1}",
+            seconds * 1000.0,
+            input.len() as f64 / MEGABYTE as f64 / seconds,
+        );
+    }
+}
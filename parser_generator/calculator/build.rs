@@ -0,0 +1,9 @@
+use parser_generator::Builder;
+
+fn main() {
+    for grammar in ["calculator", "calculator_ebnf", "calculator_left_recursive"] {
+        Builder::new(format!("../resources/{grammar}.txt")).generate().unwrap();
+    }
+    Builder::new("../resources/calculator_factored.txt").left_factoring(true).generate().unwrap();
+    Builder::new("../resources/calculator.txt").module("calculator_table").table_driven(true).generate().unwrap();
+}
@@ -2,7 +2,15 @@ use std::io;
 use graph_viz::GraphVizNode;
 use crate::calculator::{parse, ParseError};
 
-mod calculator;
+include!(concat!(env!("OUT_DIR"), "/calculator.rs"));
+include!(concat!(env!("OUT_DIR"), "/calculator_ebnf.rs"));
+include!(concat!(env!("OUT_DIR"), "/calculator_left_recursive.rs"));
+include!(concat!(env!("OUT_DIR"), "/calculator_factored.rs"));
+include!(concat!(env!("OUT_DIR"), "/calculator_table.rs"));
+
+mod calculator_macro {
+    parser_generator_macros::grammar_file!("../resources/calculator_ebnf.txt");
+}
 
 fn main() -> io::Result<()> {
     let io = io::stdin();
@@ -19,7 +27,39 @@ fn main() -> io::Result<()> {
 
 #[cfg(test)]
 mod calculator_test {
-    use crate::calculator::parse;
+    use crate::calculator::{parse, parse_with, Span, TreeBuilder};
+
+    /// Labels of the leaves and of the not terminal nodes in the order they are built.
+    #[derive(Default)]
+    struct Labels {
+        leaves: Vec<String>,
+        nodes: Vec<String>,
+    }
+
+    impl TreeBuilder for Labels {
+        type Node = ();
+
+        fn leaf(&mut self, _: usize, label: &str, _: Span) {
+            self.leaves.push(label.to_string());
+        }
+
+        fn node(&mut self, _: usize, label: &str, _: Span, _: Vec<()>) {
+            self.nodes.push(label.to_string());
+        }
+    }
+
+    #[test]
+    fn custom_tree_builder() {
+        let mut labels = Labels::default();
+        let ((), res) = parse_with("2 * (3)".to_string(), &mut labels).unwrap();
+        assert_eq!(6, res);
+        assert_eq!(vec!["Num", "Mul", "Lb", "Num", "Rb"], labels.leaves);
+        // Nodes are built once their members are, `(3)` is the `E` inside `P` before the outer `Tx`.
+        assert_eq!(
+            vec!["P", "F", "P", "F", "Tx", "T", "Ex", "E", "P", "F", "Tx", "Tx", "T", "Ex", "E", "Cx", "C", "S"],
+            labels.nodes,
+        );
+    }
 
     #[test]
     fn invalid_tokens() {
@@ -73,4 +113,151 @@ mod calculator_test {
         let (_, res) = parse("4 * 3 - 5 / 3 * 6 - (10 - (-(10 - 1))) + 4".to_string()).unwrap();
         assert_eq!(4 * 3 - 5 / 3 * 6 - (10 - (-(10 - 1))) + 4, res)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod calculator_ebnf_test {
+    use crate::calculator_ebnf::parse;
+
+    fn eval(expr: &str) -> i64 {
+        parse(expr.to_string()).unwrap().1
+    }
+
+    #[test]
+    fn invalid_expr() {
+        assert!(parse("9 + + 4".to_string()).is_err());
+        assert!(parse("sum()".to_string()).is_err());
+        assert!(parse("max()".to_string()).is_err());
+    }
+
+    #[test]
+    fn left_associative() {
+        assert_eq!(9 - 4 - 3, eval("9 - 4 - 3"));
+        assert_eq!(100 / 5 / 2, eval("100 / 5 / 2"));
+    }
+
+    #[test]
+    fn complex_expression() {
+        assert_eq!(-9, eval("4 * 3 - 5 / 3 * 6 - (10 - (-(10 - 1))) + 4"));
+    }
+
+    #[test]
+    fn comb() {
+        assert_eq!(10, eval("5 $ 2"));
+    }
+
+    #[test]
+    fn optional_factorial() {
+        assert_eq!(24, eval("4!"));
+        assert_eq!(2 + 6, eval("2 + 3!"));
+    }
+
+    #[test]
+    fn max_of_list() {
+        assert_eq!(7, eval("max(3, 7, 2 + 1)"));
+        assert_eq!(5, eval("max(5)"));
+    }
+
+    #[test]
+    fn sum_of_one_or_more() {
+        assert_eq!(6, eval("sum(1; 2; 3;)"));
+        assert_eq!(4, eval("sum(2 * 2;)"));
+    }
+}
+
+
+#[cfg(test)]
+mod calculator_left_recursive_test {
+    use crate::calculator_left_recursive::parse;
+
+    fn eval(expr: &str) -> i64 {
+        parse(expr.to_string()).unwrap().1
+    }
+
+    #[test]
+    fn invalid_expr() {
+        assert!(parse("9 + + 4".to_string()).is_err());
+        assert!(parse("9 $".to_string()).is_err());
+    }
+
+    #[test]
+    fn left_associative() {
+        assert_eq!(9 - 4 - 3, eval("9 - 4 - 3"));
+        assert_eq!(100 / 5 / 2, eval("100 / 5 / 2"));
+        assert_eq!(2 * 6 / 4, eval("2 * 6 / 4"));
+    }
+
+    #[test]
+    fn indirect_left_recursion() {
+        assert_eq!(10, eval("5 $ 2"));
+        assert_eq!(1, eval("5 $ 2 $ 10"));
+    }
+
+    #[test]
+    fn complex_expression() {
+        assert_eq!(-9, eval("4 * 3 - 5 / 3 * 6 - (10 - (-(10 - 1))) + 4"));
+    }
+}
+
+#[cfg(test)]
+mod calculator_factored_test {
+    use crate::calculator_factored::parse;
+
+    fn eval(expr: &str) -> i64 {
+        parse(expr.to_string()).unwrap().1
+    }
+
+    #[test]
+    fn invalid_expr() {
+        assert!(parse("9 $ $ 2".to_string()).is_err());
+        assert!(parse("4 !!".to_string()).is_err());
+    }
+
+    #[test]
+    fn common_prefix() {
+        assert_eq!(10, eval("5 $ 2"));
+        assert_eq!(5 + 2, eval("5 + 2"));
+        assert_eq!(24, eval("4!"));
+        assert_eq!(4, eval("4"));
+        assert_eq!(2 * 6 - 1, eval("2 * 3! - 1"));
+    }
+}
+
+#[cfg(test)]
+mod calculator_table_test {
+    use crate::calculator_table::parse;
+
+    fn eval(expr: &str) -> i64 {
+        parse(expr.to_string()).unwrap().1
+    }
+
+    #[test]
+    fn same_results_as_recursive_parser() {
+        for expr in ["2 + 3 * 4", "9 - 4 - 3", "5 $ 2", "-(2 - 7) * 3", "4 * 3 - 5 / 3 * 6 - (10 - (-(10 - 1))) + 4"] {
+            assert_eq!(crate::calculator::parse(expr.to_string()).unwrap().1, eval(expr));
+        }
+    }
+
+    #[test]
+    fn errors_list_expected_tokens() {
+        let error = parse("2 + )".to_string()).err().unwrap();
+        assert_eq!("expected one of Num, Minus, Lb but found Rb `)`", error.message);
+        assert_eq!(5, error.span.column);
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let depth = 300_000;
+        let expr = format!("{}7{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(7, eval(&expr));
+    }
+}
+
+#[cfg(test)]
+mod macro_test {
+    #[test]
+    fn grammar_file() {
+        let (_, res) = crate::calculator_macro::parse("max(1, 2 * 3) + 4!".to_string()).unwrap();
+        assert_eq!(6 + 24, res);
+    }
+}
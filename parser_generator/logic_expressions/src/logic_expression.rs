@@ -1,14 +1,14 @@
-#![allow(warnings, unused, non_snake_case, non_camel_case_types)]
 
+#![allow(warnings, unused, non_snake_case, non_camel_case_types)]
 use regex::Regex;
+use regex_automata::{meta, Anchored, Input, MatchKind, PatternSet};
 use lazy_static::lazy_static;
-use graph_viz::GraphVizNode;
 
 
 use self::SyntaxTree::*;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
-pub enum Token_ {
+pub enum Token {
     LeftBracket,
     RightBracket,
     Var,
@@ -34,452 +34,672 @@ pub enum NotTerminal {
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub enum SyntaxTree {
-    Leaf(Token_),
+    Leaf(Token),
     Tree(NotTerminal, Vec<SyntaxTree>),
 }
 
 
+
 #[derive(Debug)]
-pub enum Token {
-    Xor(String),
-    Or(String),
-    And(String),
-    Not(String),
-    Var(String),
-    LB(String),
-    RB(String),
-    Eof,
+pub enum LogicToken {
+Xor(String, LogicSpan),
+Or(String, LogicSpan),
+And(String, LogicSpan),
+Not(String, LogicSpan),
+Var(String, LogicSpan),
+LB(String, LogicSpan),
+RB(String, LogicSpan),
+Eof(LogicSpan),
+}
+impl LogicToken {
+pub fn span(&self) -> LogicSpan {
+match self {
+LogicToken::Xor(_, span, ..) => *span,
+LogicToken::Or(_, span, ..) => *span,
+LogicToken::And(_, span, ..) => *span,
+LogicToken::Not(_, span, ..) => *span,
+LogicToken::Var(_, span, ..) => *span,
+LogicToken::LB(_, span, ..) => *span,
+LogicToken::RB(_, span, ..) => *span,
+LogicToken::Eof(span, ..) => *span,
+}
+}
+fn describe(&self) -> String {
+match self {
+LogicToken::Xor(text, ..) => format!("Xor `{text}`"),
+LogicToken::Or(text, ..) => format!("Or `{text}`"),
+LogicToken::And(text, ..) => format!("And `{text}`"),
+LogicToken::Not(text, ..) => format!("Not `{text}`"),
+LogicToken::Var(text, ..) => format!("Var `{text}`"),
+LogicToken::LB(text, ..) => format!("LB `{text}`"),
+LogicToken::RB(text, ..) => format!("RB `{text}`"),
+LogicToken::Eof(..) => "Eof".to_string(),
+}
+}
+fn kind(&self) -> usize {
+match self {
+LogicToken::Xor(..) => 0,
+LogicToken::Or(..) => 1,
+LogicToken::And(..) => 2,
+LogicToken::Not(..) => 3,
+LogicToken::Var(..) => 4,
+LogicToken::LB(..) => 5,
+LogicToken::RB(..) => 6,
+LogicToken::Eof(..) => 7,
+}
+}
 }
 
-lazy_static!(
-static ref Xor_regex: Regex = Regex::new("xor").unwrap(); 
-static ref Or_regex: Regex = Regex::new("or").unwrap(); 
-static ref And_regex: Regex = Regex::new("and").unwrap(); 
-static ref Not_regex: Regex = Regex::new("not").unwrap(); 
-static ref Var_regex: Regex = Regex::new("[a-zA-Z]").unwrap(); 
-static ref LB_regex: Regex = Regex::new("\\(").unwrap(); 
-static ref RB_regex: Regex = Regex::new("\\)").unwrap(); 
-);
+#[derive(Clone, Copy)]
+enum LogicModeChange {
+    Push(usize),
+    Pop,
+    Switch(usize),
+}
+
+/// Patterns of one tokenizer mode, its tokens followed by its skip patterns, in one
+/// multi-pattern regex which finds all patterns matching at the pointer in one pass.
+/// Only the matched patterns are run again to find where their longest matches end,
+/// so `a|ab` matches all of `ab`.
+struct LogicLexerMode {
+    lexer: meta::Regex,
+    patterns: Vec<meta::Regex>,
+    /// Token made by the pattern, `None` for skip patterns.
+    tokens: Vec<Option<usize>>,
+    priorities: Vec<i32>,
+    changes: Vec<Option<LogicModeChange>>,
+}
 
+impl LogicLexerMode {
+    fn new(sources: &[&str], tokens: Vec<Option<usize>>, priorities: Vec<i32>, changes: Vec<Option<LogicModeChange>>) -> LogicLexerMode {
+        LogicLexerMode {
+            lexer: meta::Builder::new()
+                .configure(meta::Config::new().match_kind(MatchKind::All))
+                .build_many(sources)
+                .unwrap(),
+            patterns: sources
+                .iter()
+                .map(|p| meta::Builder::new().configure(meta::Config::new().match_kind(MatchKind::All)).build(p).unwrap())
+                .collect(),
+            tokens,
+            priorities,
+            changes,
+        }
+    }
+}
 lazy_static!(
-static ref SKIP_REGEX: Vec<Regex> = vec!(Regex::new("\n").unwrap(),Regex::new("\r").unwrap(),Regex::new(" ").unwrap(),);
+static ref MODES: Vec<LogicLexerMode> = vec![
+// Default
+LogicLexerMode::new(&["xor", "or", "and", "not", "[a-zA-Z]", "\\(", "\\)", "\n", "\r", " "], vec![Some(0), Some(1), Some(2), Some(3), Some(4), Some(5), Some(6), None, None, None], vec![0, 0, 0, 0, 0, 0, 0, i32::MAX, i32::MAX, i32::MAX], vec![None, None, None, None, None, None, None, None, None, None]),
+];
 );
 
-pub struct Tokenizer {
-    input: String,
-    pointer: usize,
+/// Location of a token or of a not terminal in the input.
+/// `start` and `end` are byte offsets, `line` and `column` of the start count from 1.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct LogicSpan {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
 }
 
+impl LogicSpan {
+    /// Span from the start of `self` to the end of `end`.
+    pub fn to(self, end: LogicSpan) -> LogicSpan {
+        LogicSpan { end: end.end, ..self }
+    }
+}
+pub struct LogicTokenizer {
+     input: String,
+     pointer: usize,
+     line: usize,
+     column: usize,
+     /// Stack of the modes in `MODES`, the last one is matched.
+     modes: Vec<usize>,
+}
 #[derive(Debug)]
-pub struct ParseError {
-    pub position: usize,
+pub struct LogicParseError {
+    pub span: LogicSpan,
     pub message: String,
 }
 
-impl Tokenizer {
-    fn match_token(&mut self) -> Option<Token> {
-        if let Some(m) = Xor_regex.find_at(&self.input, self.pointer) {
-            if m.start() == self.pointer {
-                self.pointer = m.end();
-                return Some(Token::Xor(self.input[m.start()..m.end()].to_string()));
-            }
-        }
-        if let Some(m) = Or_regex.find_at(&self.input, self.pointer) {
-            if m.start() == self.pointer {
-                self.pointer = m.end();
-                return Some(Token::Or(self.input[m.start()..m.end()].to_string()));
-            }
-        }
-        if let Some(m) = And_regex.find_at(&self.input, self.pointer) {
-            if m.start() == self.pointer {
-                self.pointer = m.end();
-                return Some(Token::And(self.input[m.start()..m.end()].to_string()));
+impl std::fmt::Display for LogicParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.span.line, self.span.column, self.message)
+    }
+}
+impl LogicTokenizer {
+fn token(index: usize, text: String, span: LogicSpan) -> Result<LogicToken, LogicParseError> {
+let token = match index {
+0 => LogicToken::Xor(text, span),
+1 => LogicToken::Or(text, span),
+2 => LogicToken::And(text, span),
+3 => LogicToken::Not(text, span),
+4 => LogicToken::Var(text, span),
+5 => LogicToken::LB(text, span),
+6 => LogicToken::RB(text, span),
+_ => unreachable!("token index out of range"),
+};
+Ok(token)
+}
+}
+
+
+impl LogicTokenizer {
+        fn new(input: String) -> LogicTokenizer {
+            LogicTokenizer{
+                input,
+                pointer: 0,
+                line: 1,
+                column: 1,
+                modes: vec![0],
             }
         }
-        if let Some(m) = Not_regex.find_at(&self.input, self.pointer) {
-            if m.start() == self.pointer {
-                self.pointer = m.end();
-                return Some(Token::Not(self.input[m.start()..m.end()].to_string()));
-            }
+
+        pub fn tokenize(input: String) -> Result<Vec<LogicToken>, LogicParseError> {
+            LogicTokenizer::new(input).run_tokenizing()
         }
-        if let Some(m) = Var_regex.find_at(&self.input, self.pointer) {
-            if m.start() == self.pointer {
-                self.pointer = m.end();
-                return Some(Token::Var(self.input[m.start()..m.end()].to_string()));
+
+        /// Moves the pointer to `end` and returns the span of the passed input.
+        fn advance(&mut self, end: usize) -> LogicSpan {
+            let span = LogicSpan { start: self.pointer, end, line: self.line, column: self.column };
+            for c in self.input[self.pointer..end].chars() {
+                if c == '\n' {
+                    self.line += 1;
+                    self.column = 1;
+                } else {
+                    self.column += 1;
+                }
             }
+            self.pointer = end;
+            span
         }
-        if let Some(m) = LB_regex.find_at(&self.input, self.pointer) {
-            if m.start() == self.pointer {
-                self.pointer = m.end();
-                return Some(Token::LB(self.input[m.start()..m.end()].to_string()));
+
+        /// Longest non-empty match at the pointer as `(pattern, end)`. Ties are won by the
+        /// higher priority and then by the pattern declared first.
+        /// Searches are anchored at the pointer but see the whole input, so `^` and `\b` keep their meaning.
+        fn next_match(&self, mode: &LogicLexerMode) -> Option<(usize, usize)> {
+            let input = Input::new(&self.input).range(self.pointer..).anchored(Anchored::Yes);
+            let mut matched = PatternSet::new(mode.lexer.pattern_len());
+            mode.lexer.which_overlapping_matches(&input, &mut matched);
+            let mut best: Option<(usize, usize)> = None;
+            for pattern in matched.iter() {
+                let index = pattern.as_usize();
+                let end = mode.patterns[index].search(&input).map_or(self.pointer, |m| m.end());
+                if end == self.pointer {
+                    continue;
+                }
+                let better = best.map_or(true, |(best_index, best_end)| {
+                    end > best_end || end == best_end && mode.priorities[index] > mode.priorities[best_index]
+                });
+                if better {
+                    best = Some((index, end));
+                }
             }
+            best
         }
-        if let Some(m) = RB_regex.find_at(&self.input, self.pointer) {
-            if m.start() == self.pointer {
-                self.pointer = m.end();
-                return Some(Token::RB(self.input[m.start()..m.end()].to_string()));
+
+        fn run_tokenizing(&mut self) -> Result<Vec<LogicToken>, LogicParseError> {
+            let mut tokens: Vec<LogicToken> = Vec::new();
+            while self.pointer < self.input.len() {
+                let mode = &MODES[*self.modes.last().unwrap()];
+                if let Some((index, end)) = self.next_match(mode) {
+                    let span = self.advance(end);
+                    if let Some(token) = mode.tokens[index] {
+                        let text = self.input[span.start .. span.end].to_string();
+                        tokens.push(LogicTokenizer::token(token, text, span)?);
+                    }
+                    match mode.changes[index] {
+                        Some(LogicModeChange::Push(next)) => self.modes.push(next),
+                        Some(LogicModeChange::Pop) if self.modes.len() > 1 => {
+                            self.modes.pop();
+                        }
+                        Some(LogicModeChange::Pop) => {
+                            return Err(LogicParseError { span, message: "No tokenizer mode to pop".to_string() });
+                        }
+                        Some(LogicModeChange::Switch(next)) => *self.modes.last_mut().unwrap() = next,
+                        None => {}
+                    }
+                    continue;
+                }
+                let end = self.input[self.pointer..].chars().next().map_or(self.pointer, |c| self.pointer + c.len_utf8());
+                return Err(
+                    LogicParseError {
+                        span: LogicSpan { start: self.pointer, end, line: self.line, column: self.column },
+                        message: "Expected token".to_string(),
+                    }
+                )
             }
+            let end = self.input.len();
+            tokens.push(LogicToken::Eof(self.advance(end)));
+            Ok(tokens)
         }
-        None
-    }
 }
 
 
-impl Tokenizer {
-    fn new(input: String) -> Tokenizer {
-        Tokenizer {
-            input,
-            pointer: 0,
-        }
-    }
 
-    pub fn tokenize(input: String) -> Result<Vec<Token>, ParseError> {
-        Tokenizer::new(input).run_tokenizing()
-    }
+/// Builds the parse tree: the parser calls `leaf` for every token it reads and `node` for every
+/// not terminal once its children are built. `id` numbers the nodes in the order they are started.
+pub trait LogicTreeBuilder {
+    type Node;
 
-    fn skip(&mut self) -> bool {
-        for regex in SKIP_REGEX.iter() {
-            if let Some(m) = regex.find_at(&self.input, self.pointer) {
-                let s = m.start();
-                if s == self.pointer {
-                    self.pointer = m.end();
-                    return true;
-                }
-            }
-        }
-        false
+    /// Node of a token, or the `error` node of a not terminal recovered from.
+    fn leaf(&mut self, id: usize, label: &str, span: LogicSpan) -> Self::Node;
+
+    fn node(&mut self, id: usize, label: &str, span: LogicSpan, children: Vec<Self::Node>) -> Self::Node;
+}
+
+impl<B: LogicTreeBuilder + ?Sized> LogicTreeBuilder for &mut B {
+    type Node = B::Node;
+
+    fn leaf(&mut self, id: usize, label: &str, span: LogicSpan) -> Self::Node {
+        (**self).leaf(id, label, span)
     }
 
-    fn run_tokenizing(&mut self) -> Result<Vec<Token>, ParseError> {
-        let mut tokens: Vec<Token> = Vec::new();
-        while self.pointer < self.input.len() {
-            if self.skip() {
-                continue;
-            }
-            if let Some(token) = self.match_token() {
-                tokens.push(token);
-                continue;
-            }
-            return Err(
-                ParseError {
-                    position: self.pointer,
-                    message: "Expected token".to_string(),
-                }
-            );
-        }
-        tokens.push(Token::Eof);
-        Ok(tokens)
+    fn node(&mut self, id: usize, label: &str, span: LogicSpan, children: Vec<Self::Node>) -> Self::Node {
+        (**self).node(id, label, span, children)
     }
 }
 
+/// Builds no tree, for parsing only the returns.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogicNoTree;
+
+impl LogicTreeBuilder for LogicNoTree {
+    type Node = ();
+
+    fn leaf(&mut self, id: usize, label: &str, span: LogicSpan) {}
+
+    fn node(&mut self, id: usize, label: &str, span: LogicSpan, children: Vec<()>) {}
+}
 
-struct Parser {
-    tokens: Vec<Token>,
+
+struct LogicParser<B> {
+    tokens: Vec<LogicToken>,
     pointer: usize,
     counter: usize,
+    /// Errors recovered from, only collected with the `recovery` option.
+    errors: Vec<LogicParseError>,
+    builder: B,
 }
 
-impl Parser {
-    fn new(tokens: Vec<Token>) -> Parser {
-        Parser { tokens, pointer: 0, counter: 0 }
+impl<B: LogicTreeBuilder> LogicParser<B> {
+    fn new(tokens: Vec<LogicToken>, builder: B) -> LogicParser<B> {
+        LogicParser{tokens, pointer: 0, counter: 0, errors: Vec::new(), builder}
     }
-}
 
-impl Parser {
-    fn Xor(&mut self) -> Result<(GraphVizNode, (String)), ParseError> {
-        let token = &self.tokens[self.pointer];
-        let pos = self.pointer;
-        let id = self.counter.to_string();
-        self.pointer += 1;
-        self.counter += 1;
-        match token {
-            Token::Xor(s) => Ok((GraphVizNode::new_leaf(id, "Xor".to_string()), s.clone())),
-            _ => Err(ParseError { position: pos, message: "Expected Xor".to_string() }),
-        }
-    }
-    fn LB(&mut self) -> Result<(GraphVizNode, (String)), ParseError> {
-        let token = &self.tokens[self.pointer];
-        let pos = self.pointer;
-        let id = self.counter.to_string();
-        self.pointer += 1;
-        self.counter += 1;
-        match token {
-            Token::LB(s) => Ok((GraphVizNode::new_leaf(id, "LB".to_string()), s.clone())),
-            _ => Err(ParseError { position: pos, message: "Expected LB".to_string() }),
-        }
-    }
-    fn Or(&mut self) -> Result<(GraphVizNode, (String)), ParseError> {
-        let token = &self.tokens[self.pointer];
-        let pos = self.pointer;
-        let id = self.counter.to_string();
-        self.pointer += 1;
-        self.counter += 1;
-        match token {
-            Token::Or(s) => Ok((GraphVizNode::new_leaf(id, "Or".to_string()), s.clone())),
-            _ => Err(ParseError { position: pos, message: "Expected Or".to_string() }),
-        }
-    }
-    fn And(&mut self) -> Result<(GraphVizNode, (String)), ParseError> {
-        let token = &self.tokens[self.pointer];
-        let pos = self.pointer;
-        let id = self.counter.to_string();
-        self.pointer += 1;
-        self.counter += 1;
-        match token {
-            Token::And(s) => Ok((GraphVizNode::new_leaf(id, "And".to_string()), s.clone())),
-            _ => Err(ParseError { position: pos, message: "Expected And".to_string() }),
+    /// Span of the tokens from `start` to the pointer, empty before the current token if none were read.
+    fn span_since(&self, start: usize) -> LogicSpan {
+        let first = self.tokens[start].span();
+        if self.pointer == start {
+            return LogicSpan { end: first.start, ..first };
         }
+        first.to(self.tokens[self.pointer - 1].span())
     }
-    fn RB(&mut self) -> Result<(GraphVizNode, (String)), ParseError> {
-        let token = &self.tokens[self.pointer];
-        let pos = self.pointer;
-        let id = self.counter.to_string();
-        self.pointer += 1;
-        self.counter += 1;
-        match token {
-            Token::RB(s) => Ok((GraphVizNode::new_leaf(id, "RB".to_string()), s.clone())),
-            _ => Err(ParseError { position: pos, message: "Expected RB".to_string() }),
-        }
-    }
-    fn Var(&mut self) -> Result<(GraphVizNode, (String)), ParseError> {
-        let token = &self.tokens[self.pointer];
-        let pos = self.pointer;
-        let id = self.counter.to_string();
-        self.pointer += 1;
-        self.counter += 1;
-        match token {
-            Token::Var(s) => Ok((GraphVizNode::new_leaf(id, "Var".to_string()), s.clone())),
-            _ => Err(ParseError { position: pos, message: "Expected Var".to_string() }),
-        }
-    }
-    fn Not(&mut self) -> Result<(GraphVizNode, (String)), ParseError> {
-        let token = &self.tokens[self.pointer];
-        let pos = self.pointer;
-        let id = self.counter.to_string();
-        self.pointer += 1;
-        self.counter += 1;
-        match token {
-            Token::Not(s) => Ok((GraphVizNode::new_leaf(id, "Not".to_string()), s.clone())),
-            _ => Err(ParseError { position: pos, message: "Expected Not".to_string() }),
-        }
+
+    /// Error at the current token listing the tokens which could be there instead.
+    fn expected(&self, tokens: &[&str]) -> LogicParseError {
+        let expected = match tokens {
+            [token] => token.to_string(),
+            _ => format!("one of {}", tokens.join(", ")),
+        };
+        let found = self.tokens[self.pointer].describe();
+        LogicParseError{span: self.tokens[self.pointer].span(), message: format!("expected {expected} but found {found}")}
     }
+}
 
-    fn Eof(&mut self) -> Result<(String, GraphVizNode), ParseError> {
-        let token = &self.tokens[self.pointer];
-        let pos = self.pointer;
-        let id = self.counter.to_string();
-        self.pointer += 1;
-        self.counter += 1;
-        match token {
-            Token::Eof => Ok(("Eof".to_string(), GraphVizNode::new_leaf(id, "Eof".to_string()))),
-            _ => Err(ParseError { position: pos, message: "Expected eof".to_string() })
+impl<B: LogicTreeBuilder> LogicParser<B> {
+
+    fn RB(&mut self) -> Result<(B::Node, LogicSpan, (String)), LogicParseError> {
+        let id = self.counter;
+        match &self.tokens[self.pointer] {
+            LogicToken::RB(s, span, ..) => {
+                let (span, value) = (*span, s.clone());
+                self.pointer += 1;
+                self.counter += 1;
+                Ok((self.builder.leaf(id, "RB", span), span, value))
+            }
+            _ => Err(self.expected(&["RB"])),
         }
     }
-    fn S(&mut self) -> Result<(GraphVizNode, (SyntaxTree)), ParseError> {
-        let mut tree: SyntaxTree;
-        let id = self.counter.to_string();
-        let mut children: Vec<GraphVizNode> = Vec::new();
-        self.counter += 1;
-        let token = &self.tokens[self.pointer];
-        match token {
-            Token::Var(_) | Token::Not(_) | Token::LB(_) => {
-                let (child, (X0_tree)) = self.X()?;
-                children.push(child);
-                tree = X0_tree;
+   
+    fn Or(&mut self) -> Result<(B::Node, LogicSpan, (String)), LogicParseError> {
+        let id = self.counter;
+        match &self.tokens[self.pointer] {
+            LogicToken::Or(s, span, ..) => {
+                let (span, value) = (*span, s.clone());
+                self.pointer += 1;
+                self.counter += 1;
+                Ok((self.builder.leaf(id, "Or", span), span, value))
             }
-            _ => return Err(ParseError { position: self.pointer, message: "Can't match rule".to_string() })
+            _ => Err(self.expected(&["Or"])),
         }
-        Ok((GraphVizNode::new_node(id, "S".to_string(), children), (tree)))
     }
-
-    fn X(&mut self) -> Result<(GraphVizNode, (SyntaxTree)), ParseError> {
-        let mut tree: SyntaxTree;
-        let id = self.counter.to_string();
-        let mut children: Vec<GraphVizNode> = Vec::new();
-        self.counter += 1;
-        let token = &self.tokens[self.pointer];
-        match token {
-            Token::Not(_) | Token::Var(_) | Token::LB(_) => {
-                let (child, (O0_tree)) = self.O()?;
-                children.push(child);
-                let (child, (Xx1_tree)) = self.Xx()?;
-                children.push(child);
-
-                tree = Tree(NotTerminal::X, vec!(O0_tree, Xx1_tree));
+   
+    fn Xor(&mut self) -> Result<(B::Node, LogicSpan, (String)), LogicParseError> {
+        let id = self.counter;
+        match &self.tokens[self.pointer] {
+            LogicToken::Xor(s, span, ..) => {
+                let (span, value) = (*span, s.clone());
+                self.pointer += 1;
+                self.counter += 1;
+                Ok((self.builder.leaf(id, "Xor", span), span, value))
             }
-            _ => return Err(ParseError { position: self.pointer, message: "Can't match rule".to_string() })
+            _ => Err(self.expected(&["Xor"])),
         }
-        Ok((GraphVizNode::new_node(id, "X".to_string(), children), (tree)))
     }
-
-    fn Xx(&mut self) -> Result<(GraphVizNode, (SyntaxTree)), ParseError> {
-        let mut tree: SyntaxTree;
-        let id = self.counter.to_string();
-        let mut children: Vec<GraphVizNode> = Vec::new();
-        self.counter += 1;
-        let token = &self.tokens[self.pointer];
-        match token {
-            Token::Xor(_) => {
-                let (child, (Xor0_ident)) = self.Xor()?;
-                children.push(child);
-                let (child, (O1_tree)) = self.O()?;
-                children.push(child);
-                let (child, (Xx2_tree)) = self.Xx()?;
-                children.push(child);
-
-                tree = Tree(NotTerminal::X_, vec!(Leaf(Token_::Xor), O1_tree, Xx2_tree));
-            }
-            Token::RB(_) | Token::Eof => {
-                tree = Tree(NotTerminal::X_, vec!(Leaf(Token_::Eps)));
+   
+    fn And(&mut self) -> Result<(B::Node, LogicSpan, (String)), LogicParseError> {
+        let id = self.counter;
+        match &self.tokens[self.pointer] {
+            LogicToken::And(s, span, ..) => {
+                let (span, value) = (*span, s.clone());
+                self.pointer += 1;
+                self.counter += 1;
+                Ok((self.builder.leaf(id, "And", span), span, value))
             }
-            _ => return Err(ParseError { position: self.pointer, message: "Can't match rule".to_string() })
+            _ => Err(self.expected(&["And"])),
         }
-        Ok((GraphVizNode::new_node(id, "Xx".to_string(), children), (tree)))
     }
-
-    fn O(&mut self) -> Result<(GraphVizNode, (SyntaxTree)), ParseError> {
-        let mut tree: SyntaxTree;
-        let id = self.counter.to_string();
-        let mut children: Vec<GraphVizNode> = Vec::new();
-        self.counter += 1;
-        let token = &self.tokens[self.pointer];
-        match token {
-            Token::Var(_) | Token::LB(_) | Token::Not(_) => {
-                let (child, (A0_tree)) = self.A()?;
-                children.push(child);
-                let (child, (Ox1_tree)) = self.Ox()?;
-                children.push(child);
-
-                tree = Tree(NotTerminal::O, vec!(A0_tree, Ox1_tree));
+   
+    fn Var(&mut self) -> Result<(B::Node, LogicSpan, (String)), LogicParseError> {
+        let id = self.counter;
+        match &self.tokens[self.pointer] {
+            LogicToken::Var(s, span, ..) => {
+                let (span, value) = (*span, s.clone());
+                self.pointer += 1;
+                self.counter += 1;
+                Ok((self.builder.leaf(id, "Var", span), span, value))
             }
-            _ => return Err(ParseError { position: self.pointer, message: "Can't match rule".to_string() })
+            _ => Err(self.expected(&["Var"])),
         }
-        Ok((GraphVizNode::new_node(id, "O".to_string(), children), (tree)))
     }
-
-    fn Ox(&mut self) -> Result<(GraphVizNode, (SyntaxTree)), ParseError> {
-        let mut tree: SyntaxTree;
-        let id = self.counter.to_string();
-        let mut children: Vec<GraphVizNode> = Vec::new();
-        self.counter += 1;
-        let token = &self.tokens[self.pointer];
-        match token {
-            Token::Or(_) => {
-                let (child, (Or0_ident)) = self.Or()?;
-                children.push(child);
-                let (child, (A1_tree)) = self.A()?;
-                children.push(child);
-                let (child, (Ox2_tree)) = self.Ox()?;
-                children.push(child);
-
-                tree = Tree(NotTerminal::O_, vec!(Leaf(Token_::Or), A1_tree, Ox2_tree));
+   
+    fn Not(&mut self) -> Result<(B::Node, LogicSpan, (String)), LogicParseError> {
+        let id = self.counter;
+        match &self.tokens[self.pointer] {
+            LogicToken::Not(s, span, ..) => {
+                let (span, value) = (*span, s.clone());
+                self.pointer += 1;
+                self.counter += 1;
+                Ok((self.builder.leaf(id, "Not", span), span, value))
             }
-            Token::Xor(_) | Token::Eof | Token::RB(_) => {
-                tree = Tree(NotTerminal::O_, vec!(Leaf(Token_::Eps)));
-            }
-            _ => return Err(ParseError { position: self.pointer, message: "Can't match rule".to_string() })
+            _ => Err(self.expected(&["Not"])),
         }
-        Ok((GraphVizNode::new_node(id, "Ox".to_string(), children), (tree)))
     }
-
-    fn A(&mut self) -> Result<(GraphVizNode, (SyntaxTree)), ParseError> {
-        let mut tree: SyntaxTree;
-        let id = self.counter.to_string();
-        let mut children: Vec<GraphVizNode> = Vec::new();
-        self.counter += 1;
-        let token = &self.tokens[self.pointer];
-        match token {
-            Token::Var(_) | Token::LB(_) | Token::Not(_) => {
-                let (child, (N0_tree)) = self.N()?;
-                children.push(child);
-                let (child, (Ax1_tree)) = self.Ax()?;
-                children.push(child);
-
-                tree = Tree(NotTerminal::A, vec!(N0_tree, Ax1_tree));
+   
+    fn LB(&mut self) -> Result<(B::Node, LogicSpan, (String)), LogicParseError> {
+        let id = self.counter;
+        match &self.tokens[self.pointer] {
+            LogicToken::LB(s, span, ..) => {
+                let (span, value) = (*span, s.clone());
+                self.pointer += 1;
+                self.counter += 1;
+                Ok((self.builder.leaf(id, "LB", span), span, value))
             }
-            _ => return Err(ParseError { position: self.pointer, message: "Can't match rule".to_string() })
+            _ => Err(self.expected(&["LB"])),
         }
-        Ok((GraphVizNode::new_node(id, "A".to_string(), children), (tree)))
     }
-
-    fn Ax(&mut self) -> Result<(GraphVizNode, (SyntaxTree)), ParseError> {
-        let mut tree: SyntaxTree;
-        let id = self.counter.to_string();
-        let mut children: Vec<GraphVizNode> = Vec::new();
-        self.counter += 1;
-        let token = &self.tokens[self.pointer];
-        match token {
-            Token::And(_) => {
-                let (child, (And0_ident)) = self.And()?;
-                children.push(child);
-                let (child, (N1_tree)) = self.N()?;
-                children.push(child);
-                let (child, (Ax2_tree)) = self.Ax()?;
-                children.push(child);
-
-                tree = Tree(NotTerminal::A_, vec!(Leaf(Token_::And), N1_tree, Ax2_tree));
-            }
-            Token::Or(_) | Token::RB(_) | Token::Xor(_) | Token::Eof => {
-                tree = Tree(NotTerminal::A_, vec!(Leaf(Token_::Eps)));
+   
+    fn Eof(&mut self) -> Result<(B::Node, LogicSpan, (String)), LogicParseError> {
+        let id = self.counter;
+        match &self.tokens[self.pointer] {
+            LogicToken::Eof(span, ..) => {
+                let span = *span;
+                self.pointer += 1;
+                self.counter += 1;
+                Ok((self.builder.leaf(id, "Eof", span), span, "".to_string()))
             }
-            _ => return Err(ParseError { position: self.pointer, message: "Can't match rule".to_string() })
+            _ => Err(self.expected(&["Eof"])),
         }
-        Ok((GraphVizNode::new_node(id, "Ax".to_string(), children), (tree)))
     }
+fn S(&mut self,) -> Result<(B::Node, LogicSpan, (SyntaxTree)),LogicParseError> {
+let mut tree:SyntaxTree;
+let id = self.counter;
+let span_start = self.pointer;
+let mut children: Vec<B::Node> = Vec::new();
+self.counter += 1;
+let token = &self.tokens[self.pointer];
+match token {
+LogicToken::Not(..)|LogicToken::Var(..)|LogicToken::LB(..)=> {
+let (child, X0_span, (X0_tree)) = self.X()?;
+children.push(child);
+tree = X0_tree;
+}
+_ => return Err(self.expected(&["Not", "Var", "LB"]))
+}
+let node_span = self.span_since(span_start);
+Ok((self.builder.node(id, "S", node_span, children), node_span, (tree)))
+}
 
-    fn N(&mut self) -> Result<(GraphVizNode, (SyntaxTree)), ParseError> {
-        let mut tree: SyntaxTree;
-        let id = self.counter.to_string();
-        let mut children: Vec<GraphVizNode> = Vec::new();
-        self.counter += 1;
-        let token = &self.tokens[self.pointer];
-        match token {
-            Token::Not(_) => {
-                let (child, (Not0_ident)) = self.Not()?;
-                children.push(child);
-                let (child, (T1_tree)) = self.T()?;
-                children.push(child);
-
-                tree = Tree(NotTerminal::N, vec!(Leaf(Token_::Not), T1_tree));
-            }
-            Token::LB(_) | Token::Var(_) => {
-                let (child, (T0_tree)) = self.T()?;
-                children.push(child);
+fn X(&mut self,) -> Result<(B::Node, LogicSpan, (SyntaxTree)),LogicParseError> {
+let mut tree:SyntaxTree;
+let id = self.counter;
+let span_start = self.pointer;
+let mut children: Vec<B::Node> = Vec::new();
+self.counter += 1;
+let token = &self.tokens[self.pointer];
+match token {
+LogicToken::Not(..)|LogicToken::Var(..)|LogicToken::LB(..)=> {
+let (child, O0_span, (O0_tree)) = self.O()?;
+children.push(child);
+let (child, Xx1_span, (Xx1_tree)) = self.Xx()?;
+children.push(child);
+
+       tree = Tree(NotTerminal::X, vec!(O0_tree, Xx1_tree));
+   
+}
+_ => return Err(self.expected(&["Not", "Var", "LB"]))
+}
+let node_span = self.span_since(span_start);
+Ok((self.builder.node(id, "X", node_span, children), node_span, (tree)))
+}
 
-                tree = Tree(NotTerminal::N, vec!(T0_tree));
-            }
-            _ => return Err(ParseError { position: self.pointer, message: "Can't match rule".to_string() })
-        }
-        Ok((GraphVizNode::new_node(id, "N".to_string(), children), (tree)))
-    }
+fn Xx(&mut self,) -> Result<(B::Node, LogicSpan, (SyntaxTree)),LogicParseError> {
+let mut tree:SyntaxTree;
+let id = self.counter;
+let span_start = self.pointer;
+let mut children: Vec<B::Node> = Vec::new();
+self.counter += 1;
+let token = &self.tokens[self.pointer];
+match token {
+LogicToken::Xor(..)=> {
+let (child, Xor0_span, (Xor0_ident)) = self.Xor()?;
+children.push(child);
+let (child, O1_span, (O1_tree)) = self.O()?;
+children.push(child);
+let (child, Xx2_span, (Xx2_tree)) = self.Xx()?;
+children.push(child);
+
+     tree = Tree(NotTerminal::X_, vec!(Leaf(Token::Xor), O1_tree, Xx2_tree));
+   
+}
+LogicToken::RB(..)|LogicToken::Eof(..)=> {
 
-    fn T(&mut self) -> Result<(GraphVizNode, (SyntaxTree)), ParseError> {
-        let mut tree: SyntaxTree;
-        let id = self.counter.to_string();
-        let mut children: Vec<GraphVizNode> = Vec::new();
-        self.counter += 1;
-        let token = &self.tokens[self.pointer];
-        match token {
-            Token::Var(_) => {
-                let (child, (Var0_ident)) = self.Var()?;
-                children.push(child);
-
-                tree = Tree(NotTerminal::T, vec!(Leaf(Token_::Var)));
-            }
-            Token::LB(_) => {
-                let (child, (LB0_ident)) = self.LB()?;
-                children.push(child);
-                let (child, (X1_tree)) = self.X()?;
-                children.push(child);
-                let (child, (RB2_ident)) = self.RB()?;
-                children.push(child);
-
-                tree = Tree(NotTerminal::T, vec!(Leaf(Token_::LeftBracket), X1_tree, Leaf(Token_::RightBracket)));
-            }
-            _ => return Err(ParseError { position: self.pointer, message: "Can't match rule".to_string() })
-        }
-        Ok((GraphVizNode::new_node(id, "T".to_string(), children), (tree)))
-    }
+        tree = Tree(NotTerminal::X_, vec!(Leaf(Token::Eps)));
+   
+}
+_ => return Err(self.expected(&["Xor", "RB", "Eof"]))
+}
+let node_span = self.span_since(span_start);
+Ok((self.builder.node(id, "Xx", node_span, children), node_span, (tree)))
 }
 
-pub fn parse(input: String) -> Result<(GraphVizNode, (SyntaxTree)), ParseError> { Parser::new(Tokenizer::tokenize(input)?).S() }
\ No newline at end of file
+fn O(&mut self,) -> Result<(B::Node, LogicSpan, (SyntaxTree)),LogicParseError> {
+let mut tree:SyntaxTree;
+let id = self.counter;
+let span_start = self.pointer;
+let mut children: Vec<B::Node> = Vec::new();
+self.counter += 1;
+let token = &self.tokens[self.pointer];
+match token {
+LogicToken::Var(..)|LogicToken::LB(..)|LogicToken::Not(..)=> {
+let (child, A0_span, (A0_tree)) = self.A()?;
+children.push(child);
+let (child, Ox1_span, (Ox1_tree)) = self.Ox()?;
+children.push(child);
+
+       tree = Tree(NotTerminal::O, vec!(A0_tree, Ox1_tree));
+   
+}
+_ => return Err(self.expected(&["Not", "Var", "LB"]))
+}
+let node_span = self.span_since(span_start);
+Ok((self.builder.node(id, "O", node_span, children), node_span, (tree)))
+}
+
+fn Ox(&mut self,) -> Result<(B::Node, LogicSpan, (SyntaxTree)),LogicParseError> {
+let mut tree:SyntaxTree;
+let id = self.counter;
+let span_start = self.pointer;
+let mut children: Vec<B::Node> = Vec::new();
+self.counter += 1;
+let token = &self.tokens[self.pointer];
+match token {
+LogicToken::Or(..)=> {
+let (child, Or0_span, (Or0_ident)) = self.Or()?;
+children.push(child);
+let (child, A1_span, (A1_tree)) = self.A()?;
+children.push(child);
+let (child, Ox2_span, (Ox2_tree)) = self.Ox()?;
+children.push(child);
+
+       tree = Tree(NotTerminal::O_, vec!(Leaf(Token::Or), A1_tree, Ox2_tree));
+   
+}
+LogicToken::Xor(..)|LogicToken::Eof(..)|LogicToken::RB(..)=> {
+
+       tree = Tree(NotTerminal::O_, vec!(Leaf(Token::Eps)));
+   
+}
+_ => return Err(self.expected(&["Xor", "Or", "RB", "Eof"]))
+}
+let node_span = self.span_since(span_start);
+Ok((self.builder.node(id, "Ox", node_span, children), node_span, (tree)))
+}
+
+fn A(&mut self,) -> Result<(B::Node, LogicSpan, (SyntaxTree)),LogicParseError> {
+let mut tree:SyntaxTree;
+let id = self.counter;
+let span_start = self.pointer;
+let mut children: Vec<B::Node> = Vec::new();
+self.counter += 1;
+let token = &self.tokens[self.pointer];
+match token {
+LogicToken::Var(..)|LogicToken::Not(..)|LogicToken::LB(..)=> {
+let (child, N0_span, (N0_tree)) = self.N()?;
+children.push(child);
+let (child, Ax1_span, (Ax1_tree)) = self.Ax()?;
+children.push(child);
+
+      tree = Tree(NotTerminal::A, vec!(N0_tree, Ax1_tree));
+   
+}
+_ => return Err(self.expected(&["Not", "Var", "LB"]))
+}
+let node_span = self.span_since(span_start);
+Ok((self.builder.node(id, "A", node_span, children), node_span, (tree)))
+}
+
+fn Ax(&mut self,) -> Result<(B::Node, LogicSpan, (SyntaxTree)),LogicParseError> {
+let mut tree:SyntaxTree;
+let id = self.counter;
+let span_start = self.pointer;
+let mut children: Vec<B::Node> = Vec::new();
+self.counter += 1;
+let token = &self.tokens[self.pointer];
+match token {
+LogicToken::And(..)=> {
+let (child, And0_span, (And0_ident)) = self.And()?;
+children.push(child);
+let (child, N1_span, (N1_tree)) = self.N()?;
+children.push(child);
+let (child, Ax2_span, (Ax2_tree)) = self.Ax()?;
+children.push(child);
+
+     tree = Tree(NotTerminal::A_, vec!(Leaf(Token::And), N1_tree, Ax2_tree));
+   
+}
+LogicToken::Or(..)|LogicToken::RB(..)|LogicToken::Eof(..)|LogicToken::Xor(..)=> {
+
+      tree = Tree(NotTerminal::A_, vec!(Leaf(Token::Eps)));
+    
+}
+_ => return Err(self.expected(&["Xor", "Or", "And", "RB", "Eof"]))
+}
+let node_span = self.span_since(span_start);
+Ok((self.builder.node(id, "Ax", node_span, children), node_span, (tree)))
+}
+
+fn N(&mut self,) -> Result<(B::Node, LogicSpan, (SyntaxTree)),LogicParseError> {
+let mut tree:SyntaxTree;
+let id = self.counter;
+let span_start = self.pointer;
+let mut children: Vec<B::Node> = Vec::new();
+self.counter += 1;
+let token = &self.tokens[self.pointer];
+match token {
+LogicToken::Not(..)=> {
+let (child, Not0_span, (Not0_ident)) = self.Not()?;
+children.push(child);
+let (child, T1_span, (T1_tree)) = self.T()?;
+children.push(child);
+
+     tree = Tree(NotTerminal::N, vec!(Leaf(Token::Not), T1_tree));
+   
+}
+LogicToken::LB(..)|LogicToken::Var(..)=> {
+let (child, T0_span, (T0_tree)) = self.T()?;
+children.push(child);
+
+     tree = Tree(NotTerminal::N, vec!(T0_tree));
+   
+}
+_ => return Err(self.expected(&["Not", "Var", "LB"]))
+}
+let node_span = self.span_since(span_start);
+Ok((self.builder.node(id, "N", node_span, children), node_span, (tree)))
+}
+
+fn T(&mut self,) -> Result<(B::Node, LogicSpan, (SyntaxTree)),LogicParseError> {
+let mut tree:SyntaxTree;
+let id = self.counter;
+let span_start = self.pointer;
+let mut children: Vec<B::Node> = Vec::new();
+self.counter += 1;
+let token = &self.tokens[self.pointer];
+match token {
+LogicToken::Var(..)=> {
+let (child, Var0_span, (Var0_ident)) = self.Var()?;
+children.push(child);
+
+     tree = Tree(NotTerminal::T, vec!(Leaf(Token::Var)));
+   
+}
+LogicToken::LB(..)=> {
+let (child, LB0_span, (LB0_ident)) = self.LB()?;
+children.push(child);
+let (child, X1_span, (X1_tree)) = self.X()?;
+children.push(child);
+let (child, RB2_span, (RB2_ident)) = self.RB()?;
+children.push(child);
+
+     tree = Tree(NotTerminal::T, vec!(Leaf(Token::LeftBracket), X1_tree, Leaf(Token::RightBracket)));
+   
+}
+_ => return Err(self.expected(&["Var", "LB"]))
+}
+let node_span = self.span_since(span_start);
+Ok((self.builder.node(id, "T", node_span, children), node_span, (tree)))
+}
+
+}
+/// Parses the input building the tree with `builder`.
+pub fn parse_with<B: LogicTreeBuilder>(input:String,builder:&mut B,) -> Result<(B::Node, (SyntaxTree)), LogicParseError> {
+    LogicParser::new(LogicTokenizer::tokenize(input)?, builder).S().map(|(tree, _, res)| (tree, res))
+}
+
+pub fn parse(input:String,) -> Result<((SyntaxTree)), LogicParseError> {
+    parse_with(input, &mut LogicNoTree).map(|result| result.1)
+}
@@ -54,7 +54,7 @@ fn read_input() -> String {
 
 fn main() {
     let input = read_input();
-    let syntax_tree = match parse(input).map(|(_, t)| t) {
+    let syntax_tree = match parse(input) {
         Ok(tree) => tree,
         Err(parseError) => {
             eprintln!("{}", parseError.message);
@@ -1,12 +1,11 @@
 use crate::logic_expression::NotTerminal::*;
 use crate::logic_expression::SyntaxTree::*;
-use crate::logic_expression::Token_::*;
-use crate::logic_expression::{ParseError, SyntaxTree};
+use crate::logic_expression::Token::*;
+use crate::logic_expression::{LogicParseError, SyntaxTree};
 use lazy_static::lazy_static;
 
-fn parse(str: &str) -> Result<SyntaxTree, ParseError> {
-    let s = crate::logic_expression::parse(str.to_string());
-    s.map(|(_, tree)| tree)
+fn parse(str: &str) -> Result<SyntaxTree, LogicParseError> {
+    crate::logic_expression::parse(str.to_string())
 }
 
 #[test]
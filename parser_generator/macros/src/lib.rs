@@ -0,0 +1,258 @@
+//! `grammar!` and `grammar_file!` macros generating an LL(1) parser at compile time.
+//!
+//! ```ignore
+//! mod calculator {
+//!     parser_generator_macros::grammar! {
+//!         Prelude {}
+//!         Skip { " "; }
+//!         Tokens { Num -> "[0-9]+"; Plus -> "\\+"; }
+//!         NotTerminals {
+//!             S {} {res # i64} -> Num {res = Num0_ident.parse().unwrap();} (Plus Num {res += Num1_ident.parse::<i64>().unwrap();})*;
+//!         }
+//!     }
+//! }
+//! ```
+//! Both macros expand to the tokenizer and the parser as items of the enclosing module,
+//! so the generated `Token`, `Parser` and `parse` should get a module of their own,
+//! or the grammar's `Options` section should name one.
+
+use std::fs;
+use std::path::PathBuf;
+
+use parser_generator::{generate_parser_from_string, GeneratorError, GeneratorOptions, GrammarError};
+use proc_macro2::{Delimiter, Spacing, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+use syn::LitStr;
+
+/// Generates a parser from the grammar written inside the macro.
+///
+/// Grammar errors point to the token where the grammar parser stopped, undefined and duplicate
+/// names and wrong references to the offending token, and
+/// LL(1) conflicts to the definition of the conflicting not terminal.
+#[proc_macro]
+pub fn grammar(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand_grammar(input.into()).into()
+}
+
+/// Generates a parser from a grammar file, the path is relative to the crate root.
+#[proc_macro]
+pub fn grammar_file(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand_grammar_file(input.into()).into()
+}
+
+fn expand_grammar(input: TokenStream) -> TokenStream {
+    let source = Source::new(input.clone());
+    match generate(&source.text) {
+        Ok(code) => code,
+        Err(GeneratorError::Grammar(err)) => grammar_error(&source, &err),
+        Err(GeneratorError::Validation(report)) => report.errors.iter().map(|err| grammar_error(&source, err)).collect(),
+        Err(GeneratorError::Conflicts(report)) => report
+            .conflicts
+            .iter()
+            .map(|conflict| compile_error(definition_span(&input, &conflict.not_terminal), &conflict.to_string()))
+            .collect(),
+    }
+}
+
+fn grammar_error(source: &Source, err: &GrammarError) -> TokenStream {
+    let span = source.span_at(err.column - 1);
+    let message = match &err.section {
+        Some(section) => format!("expected {} in {section} section", err.expected),
+        None => format!("expected {}", err.expected),
+    };
+    compile_error(span, &message)
+}
+
+fn expand_grammar_file(input: TokenStream) -> TokenStream {
+    let path: LitStr = match syn::parse2(input) {
+        Ok(path) => path,
+        Err(err) => return err.to_compile_error(),
+    };
+    let root = std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
+    let file = root.join(path.value());
+
+    let grammar = match fs::read_to_string(&file) {
+        Ok(grammar) => grammar,
+        Err(err) => return compile_error(path.span(), &format!("{}: {err}", file.display())),
+    };
+    let code = match generate(&grammar) {
+        Ok(code) => code,
+        Err(err) => return compile_error(path.span(), &format!("{}: {err}", path.value())),
+    };
+
+    // Makes cargo rebuild the crate when the grammar file changes.
+    let file = file.to_string_lossy();
+    quote! {
+        const _: &str = include_str!(#file);
+        #code
+    }
+}
+
+fn generate(grammar: &str) -> Result<TokenStream, GeneratorError> {
+    let parser = generate_parser_from_string(grammar.to_string(), &GeneratorOptions::default())?;
+    let code: TokenStream = parser.code.parse().expect("generated parser is valid Rust");
+    Ok(outer_attributes(code))
+}
+
+fn compile_error(span: Span, message: &str) -> TokenStream {
+    quote_spanned!(span=> compile_error!(#message);)
+}
+
+/// Grammar text rebuilt from the macro input on one line, with spans of its tokens.
+struct Source {
+    text: String,
+    /// Char offset of every token in `text` with its span, ordered by offset.
+    spans: Vec<(usize, Span)>,
+}
+
+impl Source {
+    fn new(input: TokenStream) -> Source {
+        let mut source = Source { text: String::new(), spans: Vec::new() };
+        source.push_stream(input);
+        source
+    }
+
+    fn push_stream(&mut self, input: TokenStream) {
+        let mut joint = false;
+        let mut after_group = false;
+        for tree in input {
+            // `( ... )*` needs the repetition right after the bracket.
+            let repetition = after_group && matches!(&tree, TokenTree::Punct(punct) if "*+?".contains(punct.as_char()));
+            if !joint && !repetition && !self.text.is_empty() && !self.text.ends_with(['(', '{', '[']) {
+                self.text.push(' ');
+            }
+            joint = matches!(&tree, TokenTree::Punct(punct) if punct.spacing() == Spacing::Joint);
+            after_group = matches!(&tree, TokenTree::Group(_));
+            self.spans.push((self.text.chars().count(), tree.span()));
+
+            match tree {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    self.text.push_str(open);
+                    self.push_stream(group.stream());
+                    self.spans.push((self.text.chars().count(), group.span_close()));
+                    self.text.push_str(close);
+                }
+                tree => self.text.push_str(&tree.to_string()),
+            }
+        }
+    }
+
+    /// Span of the token at or right before the char offset.
+    fn span_at(&self, offset: usize) -> Span {
+        self.spans
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= offset)
+            .map_or(Span::call_site(), |(_, span)| *span)
+    }
+}
+
+/// Span of `Name {..} {..} ->` in the `NotTerminals` section.
+///
+/// Helper not terminals generated for groups and rewrites are named
+/// `{Name}_...`, their conflicts point to the not terminal they come from.
+fn definition_span(input: &TokenStream, not_terminal: &str) -> Span {
+    let name = not_terminal.split('_').next().unwrap_or(not_terminal);
+    let trees: Vec<TokenTree> = input.clone().into_iter().collect();
+    let section = trees.windows(2).find_map(|pair| match pair {
+        [TokenTree::Ident(ident), TokenTree::Group(group)] if ident == "NotTerminals" => Some(group.stream()),
+        _ => None,
+    });
+    let Some(section) = section else {
+        return Span::call_site();
+    };
+
+    let trees: Vec<TokenTree> = section.into_iter().collect();
+    trees
+        .windows(4)
+        .find_map(|window| match window {
+            [TokenTree::Ident(ident), TokenTree::Group(args), TokenTree::Group(returns), TokenTree::Punct(arrow)]
+                if ident == name
+                    && args.delimiter() == Delimiter::Brace
+                    && returns.delimiter() == Delimiter::Brace
+                    && arrow.as_char() == '-' => Some(ident.span()),
+            _ => None,
+        })
+        .unwrap_or(Span::call_site())
+}
+
+/// Macros can't expand to inner attributes, so `#![allow(..)]` of the generated
+/// code is put before every item instead.
+fn outer_attributes(code: TokenStream) -> TokenStream {
+    let mut trees = code.into_iter().peekable();
+    let inner: Vec<TokenTree> = trees.by_ref().take(3).collect();
+    let attribute = match inner.as_slice() {
+        [TokenTree::Punct(hash), TokenTree::Punct(bang), TokenTree::Group(group)]
+            if hash.as_char() == '#' && bang.as_char() == '!' && group.delimiter() == Delimiter::Bracket =>
+            quote!(# #group),
+        _ => return inner.into_iter().chain(trees).collect(),
+    };
+
+    let mut output = TokenStream::new();
+    let mut item_start = true;
+    while let Some(tree) = trees.next() {
+        if item_start {
+            output.extend(attribute.clone());
+        }
+        let ends_item = match &tree {
+            TokenTree::Punct(punct) => punct.as_char() == ';',
+            TokenTree::Group(group) => group.delimiter() == Delimiter::Brace
+                && !matches!(trees.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == ';'),
+            _ => false,
+        };
+        output.extend([tree]);
+        item_start = ends_item;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::TokenStream;
+
+    use super::{expand_grammar, outer_attributes, Source};
+
+    fn tokens(code: &str) -> TokenStream {
+        code.parse().unwrap()
+    }
+
+    #[test]
+    fn source_keeps_joint_punctuation() {
+        let source = Source::new(tokens("Tokens { Plus -> \"\\\\+\"; } S {res # i64} -> C {res = C0_res;} (Plus C)*;"));
+        assert_eq!("Tokens {Plus -> \"\\\\+\" ;} S {res # i64} -> C {res = C0_res ;} (Plus C)*;", source.text);
+    }
+
+    #[test]
+    fn inner_attribute_is_put_before_every_item() {
+        let code = outer_attributes(tokens("#![allow(unused)] use a::b; struct A { x: i32 } impl A {} const B: A = A { x: 1 }; fn f() {}"));
+        assert_eq!(
+            tokens("#[allow(unused)] use a::b; #[allow(unused)] struct A { x: i32 } #[allow(unused)] impl A {} \
+                    #[allow(unused)] const B: A = A { x: 1 }; #[allow(unused)] fn f() {}").to_string(),
+            code.to_string(),
+        );
+    }
+
+    #[test]
+    fn errors_become_compile_errors() {
+        let malformed = expand_grammar(tokens("Prelude {} Skip {} Tokens { Num \"[0-9]+\"; } NotTerminals {}"));
+        assert!(malformed.to_string().starts_with("compile_error !"));
+        assert!(malformed.to_string().contains("in Tokens section"));
+
+        let conflicts = expand_grammar(tokens(
+            "Prelude {} Skip {} Tokens { Num -> \"[0-9]+\"; } NotTerminals { S {} {} -> Num | Num Num; }"
+        ));
+        assert!(conflicts.to_string().contains("FIRST/FIRST conflict in `S`"));
+
+        let invalid = expand_grammar(tokens(
+            "Prelude {} Skip {} Tokens { Num -> \"[0-9]+\"; } NotTerminals { S {} {} -> Nm {let _ = Num0_value;}; }"
+        ));
+        assert_eq!(2, invalid.to_string().matches("compile_error !").count());
+        assert!(invalid.to_string().contains("expected declared token or not terminal instead of `Nm` in NotTerminals section"));
+    }
+}
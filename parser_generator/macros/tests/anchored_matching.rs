@@ -0,0 +1,28 @@
+mod grammar {
+    parser_generator_macros::grammar! {
+        Prelude {}
+        Skip { " "; }
+        Tokens {
+            Hash -> "#";
+            Start -> "^#" priority 1;
+            Num -> "[0-9]+";
+            Unit -> "\\b[a-z]+";
+            Suffix -> "[a-z]+";
+        }
+        NotTerminals {
+            S {} {words # Vec<String>} -> {words = Vec::new();} (
+                Hash {words.push("hash".to_string());} |
+                Start {words.push("start".to_string());} |
+                Num {words.push(format!("num {}", Num0_ident));} |
+                Unit {words.push(format!("unit {}", Unit0_ident));} |
+                Suffix {words.push(format!("suffix {}", Suffix0_ident));}
+            )*;
+        }
+    }
+}
+
+#[test]
+fn anchored_matching_sees_whole_input() {
+    let (_, words) = grammar::parse("# 12 kg 3x #".to_string()).unwrap();
+    assert_eq!(vec!["start", "num 12", "unit kg", "num 3", "suffix x", "hash"], words);
+}
@@ -0,0 +1,32 @@
+mod grammar {
+    parser_generator_macros::grammar! {
+        Options { tree = "ast"; }
+        Prelude {}
+        Skip { " "; }
+        Tokens {
+            Num -> "[0-9]+" : i64 = |s| s.parse();
+            Plus -> "\\+";
+            Lb -> "\\(";
+            Rb -> "\\)";
+        }
+        NotTerminals {
+            S {} {} -> E^;
+            E {} {} -> lhs=E Plus! rhs=P | P^;
+            P {} {} -> Num | Lb! E^ Rb!;
+        }
+    }
+}
+
+#[test]
+fn abstract_syntax_tree() {
+    use grammar::{parse, ENode, Leaf};
+
+    let (tree, ()) = parse("1 + (2 + 3)".to_string()).unwrap();
+    let ENode::EPlusP { lhs, rhs } = tree else { panic!("{tree:?}") };
+    assert!(matches!(*lhs, ENode::Num(Leaf { value: 1, .. })));
+    let ENode::EPlusP { lhs, rhs } = *rhs else { panic!() };
+    assert!(matches!((*lhs, *rhs), (ENode::Num(Leaf { value: 2, .. }), ENode::Num(Leaf { value: 3, .. }))));
+
+    let (tree, ()) = parse("((4))".to_string()).unwrap();
+    assert!(matches!(tree, ENode::Num(Leaf { value: 4, .. })));
+}
@@ -0,0 +1,50 @@
+mod grammar {
+    parser_generator_macros::grammar! {
+        Options { tree = "cst"; }
+        Prelude {}
+        Skip { " "; }
+        Tokens {
+            Num -> "[0-9]+" : i64 = |s| s.parse();
+            Plus -> "\\+";
+            Comma -> ",";
+            Fact -> "!";
+            Lb -> "\\(";
+            Rb -> "\\)";
+        }
+        NotTerminals {
+            S {} {count # usize} -> {count = 1;} E (Comma E {count += 1;})*;
+            E {} {} -> E Plus P | P;
+            P {} {} -> Num (Fact)? | Lb E Rb;
+        }
+    }
+}
+
+#[test]
+fn concrete_syntax_tree() {
+    use grammar::{parse, ENode, Leaf, PGroup1Node, PNode, SGroup1Node, SNode, Span};
+
+    let (tree, count) = parse("1 + (2!), 3".to_string()).unwrap();
+    assert_eq!(2, count);
+    let SNode::E(sum, list) = tree;
+    let ENode::EPlusP(left, Leaf { span: plus, .. }, right) = *sum else { panic!() };
+    assert_eq!((2, 3), (plus.start, plus.end));
+    assert!(matches!(*left, ENode::P(p) if matches!(*p, PNode::Num(Leaf { value: 1, .. }, None))));
+    let PNode::LbERb(_, inner, _) = *right else { panic!() };
+    assert!(matches!(*inner, ENode::P(p) if matches!(*p, PNode::Num(Leaf { value: 2, .. }, Some(PGroup1Node::Fact(_))))));
+    let [SGroup1Node::CommaE(Leaf { span: Span { start: 8, .. }, .. }, last)] = &list[..] else { panic!("{list:?}") };
+    assert!(matches!(&**last, ENode::P(p) if matches!(**p, PNode::Num(Leaf { value: 3, .. }, None))));
+
+    let (tree, _) = parse("1, 2, 3, 4".to_string()).unwrap();
+    let SNode::E(_, list) = tree;
+    let values: Vec<i64> = list
+        .into_iter()
+        .map(|SGroup1Node::CommaE(_, e)| match *e {
+            ENode::P(p) => match *p {
+                PNode::Num(Leaf { value, .. }, _) => value,
+                p => panic!("{p:?}"),
+            },
+            e => panic!("{e:?}"),
+        })
+        .collect();
+    assert_eq!(vec![2, 3, 4], values);
+}
@@ -0,0 +1,20 @@
+mod grammar {
+    parser_generator_macros::grammar! {
+        Prelude {}
+        Skip { " "; }
+        Tokens {
+            Num -> "[0-9]+";
+            Plus -> "\\+";
+        }
+        NotTerminals {
+            S {} {res # i64} -> Num {res = Num0_ident.parse().unwrap();} (Plus Num {res += Num1_ident.parse::<i64>().unwrap();})*;
+        }
+    }
+}
+
+#[test]
+fn inline_grammar() {
+    let (_, res) = grammar::parse("1 + 2 + 39".to_string()).unwrap();
+    assert_eq!(42, res);
+    assert!(grammar::parse("1 +".to_string()).is_err());
+}
@@ -0,0 +1,47 @@
+mod labels {
+    parser_generator_macros::grammar! {
+        Options { tree = "none"; }
+        Prelude {}
+        Skip { " "; }
+        Tokens {
+            Num -> "[0-9]+" : i64 = |s| s.parse();
+            Plus -> "\\+";
+            Comma -> ",";
+            Lb -> "\\(";
+            Rb -> "\\)";
+        }
+        NotTerminals {
+            S {} {sum # i64} -> first:E {sum = first.res;} (Comma next:E {sum += next.res;})*;
+            E {} {res # i64} -> lhs:E Plus rhs:P {res = lhs.res + rhs.res;} | p:P {res = p.res;};
+            P {} {res # i64} -> n:Num {res = n.value;} | Lb inner:E Rb {res = inner.res;};
+        }
+    }
+}
+
+mod group_labels {
+    parser_generator_macros::grammar! {
+        Options { tree = "none"; }
+        Prelude {}
+        Skip { " "; }
+        Tokens {
+            Num -> "[0-9]+";
+        }
+        NotTerminals {
+            S {} {res # Vec<String>} -> n:Num {res = Vec::new();} (Num {res.push(n.ident.clone());})*;
+        }
+    }
+}
+
+#[test]
+fn labelled_members() {
+    use labels::parse;
+
+    assert_eq!(6, parse("1 + (2 + 3)".to_string()).unwrap());
+    assert_eq!(15, parse("1 + 2, 3, (4 + 5)".to_string()).unwrap());
+}
+
+#[test]
+fn outer_labels_inside_groups() {
+    let res = group_labels::parse("1 2 3".to_string()).unwrap();
+    assert_eq!(vec!["1", "1"], res);
+}
@@ -0,0 +1,43 @@
+mod grammar {
+    parser_generator_macros::grammar! {
+        Prelude {}
+        Skip { " "; }
+        Tokens {
+            Quote -> "\"" push(Str);
+            Num -> "[0-9]+";
+            Plus -> "\\+";
+        }
+        Tokens mode Str {
+            Text -> "[^\"$]+";
+            Dollar -> "\\$\\{" push(Interp);
+            EndQuote -> "\"" pop;
+        }
+        Tokens mode Interp {
+            " ";
+            Num -> "[0-9]+";
+            Plus -> "\\+";
+            Rb -> "\\}" pop;
+        }
+        NotTerminals {
+            S {} {parts # Vec<String>} -> Quote {parts = Vec::new();} (
+                Text {parts.push(Text0_ident.clone());} |
+                Dollar E Rb {parts.push(E1_res.to_string());}
+            )* EndQuote;
+            E {} {res # i64} -> Num {res = Num0_ident.parse().unwrap();} (Plus Num {res += Num1_ident.parse::<i64>().unwrap();})*;
+        }
+    }
+}
+
+#[test]
+fn modes_lex_interpolated_strings() {
+    use grammar::parse;
+
+    let (_, parts) = parse("\"abc ${1 + 2} def\"".to_string()).unwrap();
+    assert_eq!(vec!["abc ", "3", " def"], parts);
+
+    let (_, parts) = parse("  \"${4}${5 + 6}\"".to_string()).unwrap();
+    assert_eq!(vec!["4", "11"], parts);
+
+    // `}` is a token only inside `${ }`.
+    assert!(parse("\"abc\" }".to_string()).is_err());
+}
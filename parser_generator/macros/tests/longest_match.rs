@@ -0,0 +1,30 @@
+mod grammar {
+    parser_generator_macros::grammar! {
+        Prelude {}
+        Skip { " "; }
+        Tokens {
+            Or -> "or";
+            Ident -> "[a-z]+";
+            If -> "if" priority 1;
+            Le -> "<";
+            LeEq -> "<=";
+            Eq -> "=|==";
+        }
+        NotTerminals {
+            S {} {words # Vec<String>} -> {words = Vec::new();} (
+                Or {words.push("or".to_string());} |
+                Ident {words.push(format!("ident {}", Ident0_ident));} |
+                If {words.push("if".to_string());} |
+                Le {words.push("<".to_string());} |
+                LeEq {words.push("<=".to_string());} |
+                Eq {words.push(format!("eq {}", Eq0_ident));}
+            )*;
+        }
+    }
+}
+
+#[test]
+fn longest_match() {
+    let (_, words) = grammar::parse("order or if iffy <= < == =".to_string()).unwrap();
+    assert_eq!(vec!["ident order", "or", "if", "ident iffy", "<=", "<", "eq ==", "eq ="], words);
+}
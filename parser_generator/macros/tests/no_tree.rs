@@ -0,0 +1,19 @@
+mod grammar {
+    parser_generator_macros::grammar! {
+        Options { tree = "none"; }
+        Prelude {}
+        Skip { " "; }
+        Tokens {
+            Num -> "[0-9]+";
+            Plus -> "\\+";
+        }
+        NotTerminals {
+            S {} {res # i64} -> Num {res = Num0_ident.parse().unwrap();} (Plus Num {res += Num1_ident.parse::<i64>().unwrap();})*;
+        }
+    }
+}
+
+#[test]
+fn parse_without_tree() {
+    assert_eq!(6, grammar::parse("1 + 2 + 3".to_string()).unwrap());
+}
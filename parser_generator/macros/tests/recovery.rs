@@ -0,0 +1,51 @@
+mod grammar {
+    parser_generator_macros::grammar! {
+        Options { recovery = "Semi"; }
+        Prelude {}
+        Skip { " "; }
+        Tokens {
+            Num -> "[0-9]+";
+            Plus -> "\\+";
+            Semi -> ";";
+            Lb -> "\\(";
+            Rb -> "\\)";
+        }
+        NotTerminals {
+            S {} {sums # Vec<i64>} -> {sums = Vec::new();} (E Semi {sums.push(E0_res);})*;
+            E {} {res # i64} -> P {res = P0_res;} (Plus P {res += P1_res;})*;
+            P {} {res # i64} -> Num {res = Num0_ident.parse().unwrap();} | Lb E Rb {res = E1_res;};
+        }
+    }
+}
+
+#[test]
+fn recovers_from_errors() {
+    use grammar::{parse, parse_recovering};
+
+    let (result, errors) = parse_recovering("1 + 2; 3 + + 4; (5 6; 7;".to_string());
+    let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        vec![
+            "1:12: expected one of Num, Lb but found Plus `+`",
+            "1:20: expected one of Plus, Semi, Rb but found Num `6`",
+            "1:21: expected Rb but found Semi `;`",
+        ],
+        messages,
+    );
+    let (tree, sums) = result.unwrap();
+    assert_eq!(vec![3, 7, 0, 7], sums);
+    assert!(graph_viz::generate_dot_format(&tree).contains("label=error"));
+
+    // Nothing is left to skip at `Eof`, the not terminals failing there become error leaves.
+    let (result, errors) = parse_recovering("(1".to_string());
+    let (tree, sums) = result.unwrap();
+    assert!(sums.is_empty());
+    assert!(graph_viz::generate_dot_format(&tree).contains("label=error"));
+    assert_eq!(
+        vec!["1:3: expected one of Plus, Semi, Rb but found Eof", "1:3: expected Rb but found Eof", "1:3: expected Semi but found Eof"],
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+    );
+
+    assert_eq!(vec![3], parse("1 + 2;".to_string()).unwrap().1);
+    assert_eq!("1:5: expected one of Num, Lb but found Semi `;`", parse("1 + ; 2;".to_string()).err().unwrap().to_string());
+}
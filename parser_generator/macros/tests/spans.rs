@@ -0,0 +1,37 @@
+mod grammar {
+    parser_generator_macros::grammar! {
+        Prelude {}
+        Skip { " "; "\n"; }
+        Tokens {
+            Num -> "[0-9]+";
+            Plus -> "\\+";
+            Lb -> "\\(";
+            Rb -> "\\)";
+        }
+        NotTerminals {
+            S {} {spans # Vec<Span>} -> E {spans = E0_spans; spans.push(E0_span);};
+            E {} {spans # Vec<Span>} -> E Plus Num {spans = E0_spans; spans.push(E0_span); spans.push(Num2_span);} |
+                                        Num {spans = vec![Num0_span];} |
+                                        Lb {spans = Vec::new();} (Num {spans.push(Lb0_span.to(Num0_span));})* Rb;
+        }
+    }
+}
+
+#[test]
+fn spans() {
+    use grammar::{parse, Span};
+
+    let (_, spans) = parse("1 + 22\n+ 3".to_string()).unwrap();
+    let ranges: Vec<(usize, usize, usize, usize)> = spans.iter().map(|s| (s.start, s.end, s.line, s.column)).collect();
+    assert_eq!(vec![(0, 1, 1, 1), (0, 1, 1, 1), (4, 6, 1, 5), (0, 6, 1, 1), (9, 10, 2, 3), (0, 10, 1, 1)], ranges);
+
+    let (_, spans) = parse("(1 2)".to_string()).unwrap();
+    assert_eq!(vec![Span { start: 0, end: 2, line: 1, column: 1 }, Span { start: 0, end: 4, line: 1, column: 1 }], spans[..2]);
+
+    let err = parse("1 +\n+".to_string()).err().unwrap();
+    assert_eq!((2, 1), (err.span.line, err.span.column));
+    assert_eq!("2:1: expected Num but found Plus `+`", err.to_string());
+
+    let err = parse("1 + x".to_string()).err().unwrap();
+    assert_eq!((4, 5, 1, 5), (err.span.start, err.span.end, err.span.line, err.span.column));
+}
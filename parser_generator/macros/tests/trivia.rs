@@ -0,0 +1,57 @@
+mod leading {
+    parser_generator_macros::grammar! {
+        Options { trivia = "leading"; }
+        Prelude {}
+        Skip { " "; "\n"; "//[^\n]*"; }
+        Tokens {
+            Num -> "[0-9]+";
+            Plus -> "\\+";
+        }
+        NotTerminals {
+            S {} {comments # Vec<String>} -> Num {comments = Num0_trivia.iter().map(|t| t.text.clone()).collect();} (
+                Plus Num {comments.extend(Plus0_trivia.iter().chain(&Num1_trivia).map(|t| t.text.clone()));}
+            )*;
+        }
+    }
+}
+
+mod trailing {
+    parser_generator_macros::grammar! {
+        Options { trivia = "trailing"; }
+        Prelude {}
+        Skip { " "; "\n"; "//[^\n]*"; }
+        Tokens {
+            Num -> "[0-9]+" : u8 = |s| s.parse();
+            Plus -> "\\+";
+        }
+        NotTerminals {
+            S {} {} -> Num (Plus Num)*;
+        }
+    }
+}
+
+#[test]
+fn trivia_is_attached_to_tokens() {
+    use leading::{parse, Token, Tokenizer};
+
+    let source = "// sum\n1 + // two\n2 +3  ";
+    let (_, comments) = parse(source.to_string()).unwrap();
+    assert_eq!(vec!["// sum", "\n", " ", " ", "// two", "\n", " "], comments);
+
+    let tokens = Tokenizer::tokenize(source.to_string()).unwrap();
+    assert!(matches!(&tokens[4], Token::Num(text, _, trivia) if text == "3" && trivia.is_empty()));
+    assert_eq!(vec![" ", " "], tokens[5].trivia().iter().map(|t| t.text.as_str()).collect::<Vec<_>>());
+    assert_eq!(source, Tokenizer::source_text(&tokens));
+}
+
+#[test]
+fn trailing_trivia_round_trips() {
+    use trailing::Tokenizer;
+
+    let source = "  // sum\n1 + // two\n2 +3  ";
+    let tokens = Tokenizer::tokenize(source.to_string()).unwrap();
+    let trivia: Vec<Vec<&str>> = tokens.iter().map(|token| token.trivia().iter().map(|t| t.text.as_str()).collect()).collect();
+    assert_eq!(vec![vec![" ", " ", "// sum", "\n", " "], vec![" ", "// two", "\n"], vec![" "], vec![], vec![" ", " "], vec![]], trivia);
+    assert_eq!(source, Tokenizer::source_text(&tokens));
+    assert!(trailing::parse(source.to_string()).is_ok());
+}
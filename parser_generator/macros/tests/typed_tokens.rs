@@ -0,0 +1,32 @@
+mod grammar {
+    parser_generator_macros::grammar! {
+        Prelude {}
+        Skip { " "; }
+        Tokens {
+            Num -> "[0-9]+" : i64 = |s| s.parse();
+            Plus -> "\\+";
+            Char -> "'.'" : char = |s| s.trim_matches('\'').parse();
+        }
+        NotTerminals {
+            S {} {res # i64} -> Num {res = Num0_value;} (
+                Plus Num {res += Num1_value;} |
+                Char {res += Char0_value as i64;}
+            )*;
+        }
+    }
+}
+
+#[test]
+fn typed_tokens() {
+    use grammar::{parse, Token, Tokenizer};
+
+    assert_eq!(42, parse("40 + 2".to_string()).unwrap().1);
+    assert_eq!(98, parse("1 'a'".to_string()).unwrap().1);
+    assert!(matches!(Tokenizer::tokenize("7".to_string()).unwrap()[0], Token::Num(7, _)));
+
+    let err = parse("1 + 99999999999999999999".to_string()).err().unwrap();
+    assert_eq!((4, 24), (err.span.start, err.span.end));
+    assert_eq!("1:5: Invalid Num `99999999999999999999`: number too large to fit in target type", err.to_string());
+    let err = parse("1 + 'a'".to_string()).err().unwrap();
+    assert_eq!("1:5: expected Num but found Char `'a'`", err.to_string());
+}
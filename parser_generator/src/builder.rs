@@ -0,0 +1,162 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+use crate::parser_generator::{generate_parser_from_string, GeneratorError, GeneratorOptions};
+
+/// Generates a parser from a `build.rs` script.
+///
+/// ```no_run
+/// // build.rs
+/// parser_generator::Builder::new("grammar/calculator.txt").generate().unwrap();
+/// ```
+/// ```ignore
+/// // src/main.rs
+/// include!(concat!(env!("OUT_DIR"), "/calculator.rs"));
+///
+/// fn main() {
+///     let (_, res) = calculator::parse("2 + 2".to_string()).unwrap();
+/// }
+/// ```
+/// The generated code is wrapped into `pub mod {module}` named after the grammar file
+/// unless the grammar's `Options` section names the module, the file is written to
+/// `OUT_DIR` as `{module}.rs`, and cargo is told to rerun the build script when the
+/// grammar changes.
+#[derive(Debug, Clone)]
+pub struct Builder {
+    grammar: PathBuf,
+    out_dir: Option<PathBuf>,
+    module: Option<String>,
+    options: GeneratorOptions,
+}
+
+impl Builder {
+    pub fn new(grammar: impl AsRef<Path>) -> Builder {
+        Builder {
+            grammar: grammar.as_ref().to_path_buf(),
+            out_dir: None,
+            module: None,
+            options: GeneratorOptions::default(),
+        }
+    }
+
+    /// Directory of the generated file, `OUT_DIR` by default.
+    pub fn out_dir(mut self, out_dir: impl AsRef<Path>) -> Builder {
+        self.out_dir = Some(out_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Name of the generated module and file, the grammar file name without extension by default.
+    /// The `module` option of the grammar overrides it.
+    pub fn module(mut self, module: impl Into<String>) -> Builder {
+        self.module = Some(module.into());
+        self
+    }
+
+    pub fn left_factoring(mut self, left_factoring: bool) -> Builder {
+        self.options.left_factoring = left_factoring;
+        self
+    }
+
+    /// Generates a table-driven parser, see [`GeneratorOptions::table_driven`].
+    pub fn table_driven(mut self, table_driven: bool) -> Builder {
+        self.options.table_driven = table_driven;
+        self
+    }
+
+    /// Generates the parser and returns the path of the written file.
+    pub fn generate(self) -> Result<PathBuf, BuildError> {
+        println!("cargo:rerun-if-changed={}", self.grammar.display());
+
+        let out_dir = match self.out_dir {
+            Some(out_dir) => out_dir,
+            None => env::var_os("OUT_DIR").map(PathBuf::from).ok_or(BuildError::OutDir)?,
+        };
+        let module = match self.module {
+            Some(module) => module,
+            None => default_module(&self.grammar),
+        };
+
+        let grammar = fs::read_to_string(&self.grammar)
+            .map_err(|source| BuildError::Io { path: self.grammar.clone(), source })?;
+        let options = GeneratorOptions { module: Some(module), ..self.options };
+        let parser = generate_parser_from_string(grammar, &options)
+            .map_err(|error| BuildError::Generator { grammar: self.grammar.clone(), error })?;
+
+        let module = parser.module.unwrap_or_default();
+        let path = out_dir.join(format!("{module}.rs"));
+        fs::write(&path, parser.code).map_err(|source| BuildError::Io { path: path.clone(), source })?;
+        Ok(path)
+    }
+}
+
+/// File stem of the grammar as a module name, `parser_` is put before a stem starting with a digit.
+fn default_module(grammar: &Path) -> String {
+    let module = grammar
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().replace(|c: char| !c.is_alphanumeric(), "_"))
+        .unwrap_or_else(|| "parser".to_string());
+    match module.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("parser_{module}"),
+        false => module,
+    }
+}
+
+#[derive(Debug)]
+pub enum BuildError {
+    /// `OUT_DIR` is not set, the builder runs outside of a build script.
+    OutDir,
+    Io { path: PathBuf, source: io::Error },
+    Generator { grammar: PathBuf, error: GeneratorError },
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::OutDir => f.write_str("OUT_DIR is not set, use `Builder::out_dir` outside of build scripts"),
+            BuildError::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            BuildError::Generator { grammar, error } => write!(f, "{}: {error}", grammar.display()),
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::OutDir => None,
+            BuildError::Io { source, .. } => Some(source),
+            BuildError::Generator { error, .. } => Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use std::path::Path;
+
+    use super::{default_module, BuildError, Builder};
+
+    #[test]
+    fn module_is_named_after_grammar() {
+        assert_eq!("calculator_ebnf", default_module(Path::new("resources/calculator_ebnf.txt")));
+        assert_eq!("logic_expr", default_module(Path::new("logic-expr.txt")));
+        assert_eq!("parser_2024_grammar", default_module(Path::new("2024-grammar.txt")));
+    }
+
+    #[test]
+    fn writes_module_into_out_dir() {
+        let out_dir = env::temp_dir().join(format!("parser_generator_builder_{}", std::process::id()));
+        fs::create_dir_all(&out_dir).unwrap();
+
+        let path = Builder::new("resources/calculator.txt").out_dir(&out_dir).generate().unwrap();
+        assert_eq!(out_dir.join("calculator.rs"), path);
+        let code = fs::read_to_string(&path).unwrap();
+        assert!(code.starts_with("pub mod calculator {"));
+
+        let missing = Builder::new("resources/missing.txt").out_dir(&out_dir).generate();
+        assert!(matches!(missing, Err(BuildError::Io { .. })));
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
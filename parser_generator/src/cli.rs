@@ -0,0 +1,173 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use parser_generator::{ExportFormat, GeneratorOptions};
+
+pub const USAGE: &str = "\
+Usage: parser_generator <command> [options]
+
+Commands:
+    generate <grammar> -o <file>    Generate a parser into <file>
+    generate <grammar> --stdout     Print the generated parser
+    check <grammar>                 Check that the grammar is LL(1)
+    first-follow <grammar>          Print FIRST and FOLLOW sets
+    tables <grammar>                Print FIRST, FOLLOW and nullable sets and the prediction table
+    help                            Print this message
+
+Options:
+    -o, --output <file>    Output file of `generate`
+    --stdout               Print the output of `generate` instead of writing a file
+    --left-factor          Extract common prefixes of alternatives
+    --table                Generate a table-driven parser instead of recursive functions
+    --format <format>      Format of `tables`: markdown (default), html, json or csv
+";
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Output {
+    File(String),
+    Stdout,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Command {
+    Generate { grammar: String, output: Output },
+    Check { grammar: String },
+    FirstFollow { grammar: String },
+    Tables { grammar: String, format: ExportFormat },
+    Help,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cli {
+    pub command: Command,
+    pub options: GeneratorOptions,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UsageError(pub String);
+
+impl Display for UsageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for UsageError {}
+
+fn usage_error<T>(message: impl Into<String>) -> Result<T, UsageError> {
+    Err(UsageError(message.into()))
+}
+
+/// Parses command line arguments without the program name.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Cli, UsageError> {
+    let mut args = args.into_iter();
+    let mut options = GeneratorOptions::default();
+
+    let command = match args.next() {
+        None => return usage_error("missing command"),
+        Some(command) => command,
+    };
+    if matches!(command.as_str(), "help" | "-h" | "--help") {
+        return Ok(Cli { command: Command::Help, options });
+    }
+
+    let mut grammar: Option<String> = None;
+    let mut output: Option<Output> = None;
+    let mut format: Option<ExportFormat> = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Cli { command: Command::Help, options }),
+            "--left-factor" => options.left_factoring = true,
+            "--table" => options.table_driven = true,
+            "-o" | "--output" | "--stdout" if output.is_some() =>
+                return usage_error("output is given more than once"),
+            "-o" | "--output" => match args.next() {
+                Some(file) => output = Some(Output::File(file)),
+                None => return usage_error(format!("missing file after `{arg}`")),
+            },
+            "--stdout" => output = Some(Output::Stdout),
+            "--format" => match args.next().as_deref() {
+                Some("markdown") => format = Some(ExportFormat::Markdown),
+                Some("html") => format = Some(ExportFormat::Html),
+                Some("json") => format = Some(ExportFormat::Json),
+                Some("csv") => format = Some(ExportFormat::Csv),
+                Some(other) => return usage_error(format!("unknown format `{other}`")),
+                None => return usage_error("missing format after `--format`"),
+            },
+            _ if arg.starts_with('-') => return usage_error(format!("unknown option `{arg}`")),
+            _ if grammar.is_some() => return usage_error(format!("unexpected argument `{arg}`")),
+            _ => grammar = Some(arg),
+        }
+    }
+
+    let Some(grammar) = grammar else {
+        return usage_error(format!("missing grammar file for `{command}`"));
+    };
+    if format.is_some() && command != "tables" {
+        return usage_error("`--format` is for `tables`");
+    }
+    let command = match (command.as_str(), output) {
+        ("generate", Some(output)) => Command::Generate { grammar, output },
+        ("generate", None) => return usage_error("missing `-o <file>` or `--stdout` for `generate`"),
+        ("check" | "first-follow" | "tables", Some(_)) =>
+            return usage_error(format!("`{command}` doesn't write a parser, `-o` and `--stdout` are for `generate`")),
+        ("check", None) => Command::Check { grammar },
+        ("first-follow", None) => Command::FirstFollow { grammar },
+        ("tables", None) => Command::Tables { grammar, format: format.unwrap_or(ExportFormat::Markdown) },
+        _ => return usage_error(format!("unknown command `{command}`")),
+    };
+    Ok(Cli { command, options })
+}
+
+#[cfg(test)]
+mod tests {
+    use parser_generator::ExportFormat;
+    use super::{parse_args, Cli, Command, Output, UsageError};
+
+    fn parse(args: &[&str]) -> Result<Cli, UsageError> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn generate_into_file_or_stdout() {
+        let cli = parse(&["generate", "calc.txt", "-o", "calc.rs", "--left-factor"]).unwrap();
+        assert_eq!(Command::Generate { grammar: "calc.txt".to_string(), output: Output::File("calc.rs".to_string()) }, cli.command);
+        assert!(cli.options.left_factoring);
+
+        let cli = parse(&["generate", "--stdout", "calc.txt"]).unwrap();
+        assert_eq!(Command::Generate { grammar: "calc.txt".to_string(), output: Output::Stdout }, cli.command);
+        assert!(!cli.options.left_factoring);
+        assert!(!cli.options.table_driven);
+
+        let cli = parse(&["generate", "calc.txt", "--table", "--stdout"]).unwrap();
+        assert!(cli.options.table_driven);
+    }
+
+    #[test]
+    fn check_and_first_follow() {
+        assert_eq!(Command::Check { grammar: "calc.txt".to_string() }, parse(&["check", "calc.txt"]).unwrap().command);
+        assert_eq!(Command::FirstFollow { grammar: "calc.txt".to_string() }, parse(&["first-follow", "calc.txt"]).unwrap().command);
+        assert_eq!(Command::Help, parse(&["check", "--help"]).unwrap().command);
+    }
+
+    #[test]
+    fn tables_in_a_format() {
+        assert_eq!(Command::Tables { grammar: "calc.txt".to_string(), format: ExportFormat::Markdown }, parse(&["tables", "calc.txt"]).unwrap().command);
+        assert_eq!(Command::Tables { grammar: "calc.txt".to_string(), format: ExportFormat::Json }, parse(&["tables", "--format", "json", "calc.txt"]).unwrap().command);
+        assert!(parse(&["tables", "calc.txt", "--format", "xml"]).is_err());
+        assert!(parse(&["tables", "calc.txt", "--format"]).is_err());
+        assert!(parse(&["check", "calc.txt", "--format", "csv"]).is_err());
+    }
+
+    #[test]
+    fn usage_errors() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["generate", "calc.txt"]).is_err());
+        assert!(parse(&["generate", "calc.txt", "-o"]).is_err());
+        assert!(parse(&["generate", "calc.txt", "-o", "calc.rs", "--stdout"]).is_err());
+        assert!(parse(&["check", "calc.txt", "--stdout"]).is_err());
+        assert!(parse(&["check", "a.txt", "b.txt"]).is_err());
+        assert!(parse(&["check", "calc.txt", "--verbose"]).is_err());
+        assert!(parse(&["build", "calc.txt"]).is_err());
+    }
+}
@@ -0,0 +1,15 @@
+//! LL(1) parser generator.
+//!
+//! [`Builder`] generates a parser from a `build.rs` script, [`generate_parser_from_string`],
+//! [`first_follow_from_string`] and [`tables_from_string`] work with a grammar in memory.
+
+mod parser_generator;
+mod builder;
+
+pub use crate::builder::{BuildError, Builder};
+pub use crate::parser_generator::{
+    first_follow_from_string, generate_parser_from_string, tables_from_string, Alternative, Conflict, ConflictKind,
+    ConflictReport, ExportFormat, FirstFollow, FollowChain, GeneratedParser, GeneratorError, GeneratorOptions,
+    GrammarError, GrammarTables, LeftRecursionReport, LeftRecursionRewrite, NotTerminalSets, PredictionRow,
+    PredictionTable, ValidationReport,
+};
@@ -1,29 +1,104 @@
-mod parser_generator;
+mod cli;
 
-use std::{fs, io};
-use std::fs::File;
+use std::{env, fs, io, process};
 use std::io::Write;
-use crate::parser_generator::generate_parser_from_string;
+use crate::cli::{parse_args, Cli, Command, Output, USAGE};
+use parser_generator::{first_follow_from_string, generate_parser_from_string, tables_from_string, GeneratedParser, GeneratorOptions};
 
-fn main() -> io::Result<()> {
+/// The grammar is malformed or not LL(1).
+const EXIT_INVALID_GRAMMAR: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+/// A file couldn't be read or written.
+const EXIT_IO: i32 = 3;
 
-    println!("Input grammar file");
-    let mut file_in = String::new();
-    let stdin = io::stdin();
+fn main() {
+    let cli = match parse_args(env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            process::exit(EXIT_USAGE);
+        }
+    };
 
-    stdin.read_line(&mut file_in)?;
-    file_in = file_in.trim().to_string();
-
-    println!("Output file");
-    let mut file_out = String::new();
-    stdin.read_line(&mut file_out)?;
-    file_out = file_out.trim().to_string();
+    if let Err(code) = run(cli) {
+        process::exit(code);
+    }
+}
 
-    let file_in_content = fs::read_to_string(file_in)?;
-    let parser = generate_parser_from_string(file_in_content);
+fn run(Cli { command, options }: Cli) -> Result<(), i32> {
+    match command {
+        Command::Generate { grammar, output } => {
+            let parser = generate(&grammar, &options)?;
+            let written = match &output {
+                Output::File(file) => fs::write(file, parser.code),
+                Output::Stdout => io::stdout().write_all(parser.code.as_bytes()),
+            };
+            written.map_err(|err| {
+                let target = match &output {
+                    Output::File(file) => file.as_str(),
+                    Output::Stdout => "stdout",
+                };
+                eprintln!("{target}: {err}");
+                EXIT_IO
+            })
+        }
+        Command::Check { grammar } => {
+            generate(&grammar, &options)?;
+            println!("{grammar}: grammar is LL(1)");
+            Ok(())
+        }
+        Command::FirstFollow { grammar } => {
+            let content = read_grammar(&grammar)?;
+            match first_follow_from_string(&content, &options) {
+                Ok(sets) => {
+                    print!("{sets}");
+                    Ok(())
+                }
+                Err(err) => {
+                    eprintln!("{grammar}: {err}");
+                    Err(EXIT_INVALID_GRAMMAR)
+                }
+            }
+        }
+        Command::Tables { grammar, format } => {
+            let content = read_grammar(&grammar)?;
+            match tables_from_string(&content, &options) {
+                Ok(tables) => {
+                    print!("{}", tables.export(format));
+                    Ok(())
+                }
+                Err(err) => {
+                    eprintln!("{grammar}: {err}");
+                    Err(EXIT_INVALID_GRAMMAR)
+                }
+            }
+        }
+        Command::Help => {
+            print!("{USAGE}");
+            Ok(())
+        }
+    }
+}
 
-    let mut file = File::create(file_out)?;
-    file.write_all(&parser.into_bytes())?;
+fn read_grammar(grammar: &str) -> Result<String, i32> {
+    fs::read_to_string(grammar).map_err(|err| {
+        eprintln!("{grammar}: {err}");
+        EXIT_IO
+    })
+}
 
-    Ok(())
+fn generate(grammar: &str, options: &GeneratorOptions) -> Result<GeneratedParser, i32> {
+    let content = read_grammar(grammar)?;
+    match generate_parser_from_string(content, options) {
+        Ok(parser) => {
+            if !parser.left_recursion.is_empty() {
+                eprint!("{grammar}: left recursion rewritten:\n{}", parser.left_recursion);
+            }
+            Ok(parser)
+        }
+        Err(err) => {
+            eprintln!("{grammar}: {err}");
+            Err(EXIT_INVALID_GRAMMAR)
+        }
+    }
 }
@@ -0,0 +1,213 @@
+use std::collections::{HashMap, HashSet};
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::parser_generator::names::Names;
+use crate::parser_generator::parser_description::{GrammarOptions, ParseTree, ParserDescription, Rule, RuleMember, RuleToken, Token, Typed};
+use crate::parser_generator::syntax_tree::tree_return;
+use crate::parser_generator::tokenizer_generator::EOF_TOKEN;
+
+lazy_static! {
+    static ref IDENT_REGEX: Regex = Regex::new("[A-Za-z_][A-Za-z0-9_]*").unwrap();
+}
+
+/// Prefix of the variables holding results of the rule member, e.g. `E1_` for `E1_res`.
+pub fn member_prefix(name: &str, index: usize) -> String {
+    format!("{name}{index}_")
+}
+
+/// Results of a token: its text as `ident`, or its converted `value` when the token declares a value type,
+/// and with the `trivia` option the skipped text attached to it.
+pub fn token_results(token: &Token, options: &GrammarOptions) -> Vec<Typed> {
+    let mut results = match &token.payload {
+        Some(payload) => vec!(Typed { name: "value".to_string(), ty: payload.ty.clone() }),
+        None => vec!(Typed { name: "ident".to_string(), ty: "String".to_string() }),
+    };
+    if options.trivia.is_some() {
+        results.push(Typed { name: "trivia".to_string(), ty: format!("Vec<{}>", Names::new(options).trivia) });
+    }
+    results
+}
+
+/// Results every call member binds as `{Name}{index}_{result}`: returns of
+/// not terminals, `ident` or `value` and `trivia` of tokens and the `span` of both.
+pub fn member_results(parser_description: &ParserDescription) -> HashMap<String, Vec<Typed>> {
+    let span_type = Names::new(&parser_description.options).span;
+    parser_description
+        .tokens
+        .iter()
+        .map(|t| (t.name.clone(), with_span(&token_results(t, &parser_description.options), &span_type)))
+        .chain(parser_description.not_terminal.iter().map(|t| (t.name.clone(), with_span(&t.returns, &span_type))))
+        .collect()
+}
+
+/// Results actions can read: [`member_results`], `ident` and `span` of `Eof` and the
+/// tree of every not terminal when the grammar builds one.
+pub fn readable_results(parser_description: &ParserDescription) -> HashMap<String, Vec<Typed>> {
+    let mut results = member_results(parser_description);
+    let span_type = Names::new(&parser_description.options).span;
+    let eof = vec![Typed { name: "ident".to_string(), ty: "String".to_string() }];
+    results.insert(EOF_TOKEN.to_string(), with_span(&eof, &span_type));
+    let tree = parser_description.options.tree;
+    if matches!(tree, ParseTree::Cst | ParseTree::Ast) {
+        for not_term in &parser_description.not_terminal {
+            if let Some(not_term_results) = results.get_mut(&not_term.name) {
+                not_term_results.push(Typed { name: tree_return(tree).to_string(), ty: String::new() });
+            }
+        }
+    }
+    results
+}
+
+pub fn with_span(returns: &[Typed], span_type: &str) -> Vec<Typed> {
+    let mut results = returns.to_vec();
+    results.push(Typed { name: "span".to_string(), ty: span_type.to_string() });
+    results
+}
+
+pub fn identifiers(code: &str) -> impl Iterator<Item = &str> {
+    IDENT_REGEX.find_iter(code).map(|m| m.as_str())
+}
+
+/// Identifiers with their offsets in the code.
+pub fn identifier_positions(code: &str) -> impl Iterator<Item = (usize, &str)> {
+    IDENT_REGEX.find_iter(code).map(|m| (m.start(), m.as_str()))
+}
+
+/// Whether the first use of `ident` in the code gives it a new value, by a statement declaring
+/// or assigning it, without reading it, at the top level of the code. `None` when the code doesn't use it.
+pub fn overwrites_first(code: &str, ident: &str) -> Option<bool> {
+    let found = IDENT_REGEX.find_iter(code).find(|m| m.as_str() == ident)?;
+    let mut before = code[..found.start()].trim_end();
+    let mut declared = false;
+    let without_mut = before.strip_suffix("mut").map(str::trim_end).unwrap_or(before);
+    if let Some(rest) = without_mut.strip_suffix("let") {
+        before = rest.trim_end();
+        declared = true;
+    }
+    let statement_start = before.is_empty() || before.ends_with(';');
+    let top_level = before.matches('{').count() == before.matches('}').count();
+
+    let after = code[found.end()..].trim_start();
+    let assigned = after.starts_with('=') && !after.starts_with("==");
+    let value = after.split(';').next().unwrap_or("");
+    let reads_itself = identifiers(value).any(|i| i == ident);
+    Some(statement_start && top_level && (declared || assigned) && !reads_itself)
+}
+
+/// Replaces every identifier in action code for which `rewrite` returns a new name.
+pub fn rewrite_identifiers(code: &str, rewrite: impl Fn(&str) -> Option<String>) -> String {
+    IDENT_REGEX
+        .replace_all(code, |caps: &Captures| rewrite(&caps[0]).unwrap_or_else(|| caps[0].to_string()))
+        .into_owned()
+}
+
+/// Replaces identifier prefixes in action code, e.g. `E1_` to `E2_`.
+pub fn rename_prefixes(code: &str, renames: &[(String, String)]) -> String {
+    if renames.is_empty() {
+        return code.to_string();
+    }
+    rewrite_identifiers(code, |ident| renamed_prefix(ident, renames))
+}
+
+pub fn renamed_prefix(ident: &str, renames: &[(String, String)]) -> Option<String> {
+    renames
+        .iter()
+        .find_map(|(from, to)| ident.strip_prefix(from.as_str()).map(|rest| format!("{to}{rest}")))
+}
+
+/// Rewrites identifiers in commands and call arguments of the members.
+pub fn rewrite_member_identifiers(members: &mut [RuleMember], rewrite: &impl Fn(&str) -> Option<String>) {
+    for member in members {
+        match member {
+            RuleMember::RuleCall(token) => token.args = rewrite_identifiers(&token.args, rewrite),
+            RuleMember::Command(code) => *code = rewrite_identifiers(code, rewrite),
+            RuleMember::Group(group) => {
+                for alternative in &mut group.alternatives {
+                    rewrite_member_identifiers(&mut alternative.members, rewrite);
+                }
+            }
+        }
+    }
+}
+
+/// Prefix renames for call members starting from `start`, moved to `new_index(i)`.
+pub fn moved_prefixes(members: &[RuleMember], start: usize, new_index: impl Fn(usize) -> usize) -> Vec<(String, String)> {
+    members
+        .iter()
+        .enumerate()
+        .skip(start)
+        .filter_map(|(i, member)| match member {
+            RuleMember::RuleCall(RuleToken { name, .. }) =>
+                Some((member_prefix(name, i), member_prefix(name, new_index(i)))),
+            _ => None,
+        })
+        .filter(|(from, to)| from != to)
+        .collect()
+}
+
+/// Identifiers used by commands and call arguments of the members, groups included.
+pub fn used_identifiers(members: &[RuleMember]) -> HashSet<String> {
+    let mut used = HashSet::new();
+    collect_identifiers(members, &mut used);
+    used
+}
+
+fn collect_identifiers(members: &[RuleMember], used: &mut HashSet<String>) {
+    for member in members {
+        match member {
+            RuleMember::RuleCall(RuleToken { args, .. }) => used.extend(identifiers(args).map(str::to_string)),
+            RuleMember::Command(code) => used.extend(identifiers(code).map(str::to_string)),
+            RuleMember::Group(group) => {
+                for alternative in &group.alternatives {
+                    collect_identifiers(&alternative.members, used);
+                }
+            }
+        }
+    }
+}
+
+/// Renumbers references to members after `index`, as if one more member
+/// was inserted right after it.
+pub fn shift_references_after(members: &mut [RuleMember], index: usize) {
+    let renames = moved_prefixes(members, index + 1, |i| i + 1);
+
+    for member in members.iter_mut() {
+        rename_in_member(member, &renames);
+    }
+}
+
+fn rename_in_member(member: &mut RuleMember, renames: &[(String, String)]) {
+    match member {
+        RuleMember::RuleCall(token) => token.args = rename_prefixes(&token.args, renames),
+        RuleMember::Command(code) => *code = rename_prefixes(code, renames),
+        RuleMember::Group(group) => {
+            for alternative in &mut group.alternatives {
+                let visible = get_visible_renames(alternative, renames);
+                for member in &mut alternative.members {
+                    rename_in_member(member, &visible);
+                }
+            }
+        }
+    }
+}
+
+/// Members of a group shadow outer members with the same prefix.
+fn get_visible_renames(alternative: &Rule, renames: &[(String, String)]) -> Vec<(String, String)> {
+    let local: HashSet<String> = alternative
+        .members
+        .iter()
+        .enumerate()
+        .filter_map(|(i, member)| match member {
+            RuleMember::RuleCall(RuleToken { name, .. }) => Some(member_prefix(name, i)),
+            _ => None,
+        })
+        .collect();
+
+    renames
+        .iter()
+        .filter(|(from, _)| !local.contains(from))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,306 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::parser_generator::action_code::{member_prefix, member_results, shift_references_after, used_identifiers, with_span};
+use crate::parser_generator::labels::label_arg_prefix;
+use crate::parser_generator::names::Names;
+use crate::parser_generator::parser_description::{
+    Annotation, Group, NotTerminal, ParserDescription, Repetition, Rule, RuleMember, RuleToken, Typed,
+};
+
+/// Attributes of the enclosing rule which are visible inside a group.
+///
+/// `args` are passed to the helper not terminal, `returns` are passed in,
+/// updated by the group actions and written back after the call. `labelled`
+/// are the results of labelled members passed to arguments of another name.
+struct Threaded {
+    args: Vec<Typed>,
+    returns: Vec<Typed>,
+    labelled: HashMap<String, String>,
+}
+
+impl Threaded {
+    /// Arguments of the call of the helper, by the enclosing rule when `outer`, else by the helper itself.
+    fn call_args(&self, outer: bool) -> String {
+        let args = self.args
+            .iter()
+            .map(|Typed { name, .. }| {
+                if self.returns.iter().any(|r| &r.name == name) {
+                    name.clone()
+                } else {
+                    let value = self.labelled.get(name).filter(|_| outer).unwrap_or(name);
+                    format!("{value}.clone()")
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("({args})")
+    }
+
+    fn write_back(&self, helper: &str, index: usize) -> Option<RuleMember> {
+        if self.returns.is_empty() {
+            return None;
+        }
+        let prefix = member_prefix(helper, index);
+        let assignments = self.returns
+            .iter()
+            .map(|Typed { name, .. }| format!("{name} = {prefix}{name};"))
+            .collect::<Vec<String>>()
+            .join(" ");
+        Some(RuleMember::Command(assignments))
+    }
+
+    fn call(&self, helper: &str, index: usize, annotation: Annotation, outer: bool) -> Vec<RuleMember> {
+        let call = RuleMember::RuleCall(RuleToken { name: helper.to_string(), args: self.call_args(outer), annotation, position: 0 });
+        let mut members = vec![call];
+        members.extend(self.write_back(helper, index));
+        members
+    }
+}
+
+struct Desugarer {
+    /// Results of the members, see [`member_results`].
+    returns: HashMap<String, Vec<Typed>>,
+    span_type: String,
+    roots: HashMap<String, String>,
+    counters: HashMap<String, usize>,
+    queue: VecDeque<NotTerminal>,
+}
+
+/// Replaces `( ... )`, `( ... )?`, `( ... )*` and `( ... )+` groups with calls of fresh
+/// helper not terminals named `{NotTerminal}_{n}_{group|opt|star|plus}`.
+///
+/// Members inside a group are numbered from zero in their alternative. Arguments and
+/// returns of the enclosing not terminal and results of the members before the group
+/// are threaded through the helpers, so group actions can read them and update returns,
+/// e.g. push into a `Vec` initialized before the group.
+pub fn desugar_groups(mut parser_description: ParserDescription) -> ParserDescription {
+    let returns = member_results(&parser_description);
+    let span_type = Names::new(&parser_description.options).span;
+    let not_terminal = std::mem::take(&mut parser_description.not_terminal);
+
+    let mut desugarer = Desugarer {
+        returns,
+        span_type,
+        roots: HashMap::new(),
+        counters: HashMap::new(),
+        queue: not_terminal.into(),
+    };
+
+    let mut desugared = Vec::new();
+    while let Some(mut not_term) = desugarer.queue.pop_front() {
+        let mut rules = std::mem::take(&mut not_term.rules);
+        for rule in &mut rules {
+            desugarer.desugar_rule(&not_term, rule);
+        }
+        not_term.rules = rules;
+        desugared.push(not_term);
+    }
+
+    ParserDescription { not_terminal: desugared, ..parser_description }
+}
+
+impl Desugarer {
+    fn desugar_rule(&mut self, not_term: &NotTerminal, rule: &mut Rule) {
+        let mut i = 0;
+        while i < rule.members.len() {
+            if let RuleMember::Group(group) = &rule.members[i] {
+                let group = group.clone();
+                let threaded = self.get_threaded(not_term, &rule.members[..i], &group);
+                let annotation = group.annotation.clone();
+                let helper = self.add_helpers(not_term, group, &threaded);
+
+                let mut call = threaded.call(&helper, i, annotation, true).into_iter();
+                rule.members[i] = call.next().unwrap();
+                if let Some(write_back) = call.next() {
+                    shift_references_after(&mut rule.members, i);
+                    rule.members.insert(i + 1, write_back);
+                    i += 1;
+                }
+            }
+            i += 1;
+        }
+    }
+
+    fn get_threaded(&self, not_term: &NotTerminal, before: &[RuleMember], group: &Group) -> Threaded {
+        let used = used_identifiers(&[RuleMember::Group(group.clone())]);
+        let mut threaded = Threaded { args: Vec::new(), returns: Vec::new(), labelled: HashMap::new() };
+
+        for typed in &not_term.returns {
+            if used.contains(&typed.name) {
+                threaded.args.push(typed.clone());
+                threaded.returns.push(typed.clone());
+            }
+        }
+
+        for typed in &not_term.args {
+            if used.contains(&typed.name) && !threaded.args.iter().any(|t| t.name == typed.name) {
+                threaded.args.push(typed.clone());
+            }
+        }
+
+        for (i, member) in before.iter().enumerate() {
+            let RuleMember::RuleCall(RuleToken { name, annotation, .. }) = member else {
+                continue;
+            };
+            let Some(returns) = self.returns.get(name) else {
+                continue;
+            };
+            let prefix = member_prefix(name, i);
+            for Typed { name, ty } in returns {
+                let result = format!("{prefix}{name}");
+                if used.contains(&result) {
+                    threaded.args.push(Typed { name: result.clone(), ty: ty.clone() });
+                }
+                let Some(label) = &annotation.label else {
+                    continue;
+                };
+                // The label shadows one of an enclosing alternative threaded under the same name.
+                let arg = format!("{}{name}", label_arg_prefix(label));
+                if used.contains(&arg) {
+                    threaded.args.retain(|typed| typed.name != arg);
+                    threaded.args.push(Typed { name: arg.clone(), ty: ty.clone() });
+                    threaded.labelled.insert(arg, result);
+                }
+            }
+        }
+
+        threaded
+    }
+
+    /// Adds helper not terminals for the group and returns the name of the one to call.
+    fn add_helpers(&mut self, parent: &NotTerminal, group: Group, threaded: &Threaded) -> String {
+        let root = self.roots.get(&parent.name).cloned().unwrap_or(parent.name.clone());
+        let Group { alternatives, repetition, .. } = group;
+
+        let helper = self.next_name(&root, repetition);
+        let rules = match repetition {
+            Repetition::Once => alternatives,
+            Repetition::Optional => {
+                let mut rules = alternatives;
+                rules.push(Rule { members: Vec::new() });
+                rules
+            }
+            Repetition::ZeroOrMore => {
+                let mut rules = append_call(alternatives, &helper, threaded);
+                rules.push(Rule { members: Vec::new() });
+                rules
+            }
+            Repetition::OneOrMore => {
+                let star = self.next_name(&root, Repetition::ZeroOrMore);
+                let mut star_rules = append_call(alternatives.clone(), &star, threaded);
+                star_rules.push(Rule { members: Vec::new() });
+                self.add_not_terminal(&root, &star, star_rules, threaded, parent.position);
+
+                append_call(alternatives, &star, threaded)
+            }
+        };
+        self.add_not_terminal(&root, &helper, rules, threaded, parent.position);
+        helper
+    }
+
+    fn add_not_terminal(&mut self, root: &str, name: &str, rules: Vec<Rule>, threaded: &Threaded, position: usize) {
+        self.roots.insert(name.to_string(), root.to_string());
+        self.returns.insert(name.to_string(), with_span(&threaded.returns, &self.span_type));
+        self.queue.push_back(NotTerminal {
+            name: name.to_string(),
+            args: threaded.args.clone(),
+            returns: threaded.returns.clone(),
+            rules,
+            position,
+        });
+    }
+
+    fn next_name(&mut self, root: &str, repetition: Repetition) -> String {
+        let counter = self.counters.entry(root.to_string()).or_insert(0);
+        *counter += 1;
+        let kind = match repetition {
+            Repetition::Once => "group",
+            Repetition::Optional => "opt",
+            Repetition::ZeroOrMore => "star",
+            Repetition::OneOrMore => "plus",
+        };
+        format!("{root}_{counter}_{kind}")
+    }
+}
+
+fn append_call(alternatives: Vec<Rule>, helper: &str, threaded: &Threaded) -> Vec<Rule> {
+    alternatives
+        .into_iter()
+        .map(|Rule { mut members }| {
+            let index = members.len();
+            members.extend(threaded.call(helper, index, Annotation::default(), false));
+            Rule { members }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_generator::parser_description::{parse_parser_description, NotTerminal, RuleMember, RuleToken};
+    use crate::parser_generator::labels::resolve_labels;
+    use super::desugar_groups;
+
+    fn desugar(not_terminals: &str) -> Vec<NotTerminal> {
+        let grammar = format!(
+            "Prelude {{}} Skip {{}} Tokens {{ Num -> \"[0-9]+\"; Plus -> \"\\\\+\"; Lb -> \"\\\\(\"; Rb -> \"\\\\)\"; }} NotTerminals {{ {not_terminals} }}"
+        );
+        desugar_groups(parse_parser_description(&grammar).unwrap()).not_terminal
+    }
+
+    fn members(not_term: &NotTerminal, rule: usize) -> Vec<String> {
+        not_term.rules[rule].members
+            .iter()
+            .map(|member| match member {
+                RuleMember::RuleCall(RuleToken { name, args, .. }) => format!("{name}{args}"),
+                RuleMember::Command(code) => format!("{{{code}}}"),
+                RuleMember::Group(_) => "group".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn star_threads_returns_and_renumbers_later_members() {
+        let not_terminals = desugar(
+            "S {} {res # i64} -> Lb {res = 0;} (Num {res += Num0_ident.parse::<i64>().unwrap();})* Rb {println!(\"{}\", Rb3_ident);};"
+        );
+        assert_eq!(2, not_terminals.len());
+        assert_eq!(
+            vec!["Lb()", "{res = 0;}", "S_1_star(res)", "{res = S_1_star2_res;}", "Rb()", "{println!(\"{}\", Rb4_ident);}"],
+            members(&not_terminals[0], 0),
+        );
+
+        let star = &not_terminals[1];
+        assert_eq!("S_1_star", star.name);
+        assert_eq!(vec!["res"], star.args.iter().map(|t| t.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(vec!["res"], star.returns.iter().map(|t| t.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(
+            vec!["Num()", "{res += Num0_ident.parse::<i64>().unwrap();}", "S_1_star(res)", "{res = S_1_star2_res;}"],
+            members(star, 0),
+        );
+        assert!(star.rules[1].members.is_empty());
+    }
+
+    #[test]
+    fn plus_uses_star_helper_and_reads_outer_members() {
+        let not_terminals = desugar("S {} {} -> Num (Plus Num {println!(\"{}{}\", Num0_ident, Num1_ident);})+;");
+        assert_eq!(vec!["S_2_star", "S_1_plus"], not_terminals[1..].iter().map(|t| t.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(vec!["Num()", "S_1_plus(Num0_ident.clone())"], members(&not_terminals[0], 0));
+        assert_eq!(vec!["Plus()", "Num()", "{println!(\"{}{}\", Num0_ident, Num1_ident);}", "S_2_star(Num0_ident.clone())"], members(&not_terminals[2], 0));
+        assert_eq!(2, not_terminals[1].rules.len());
+    }
+
+    #[test]
+    fn labels_of_enclosing_alternative_are_threaded_under_their_own_names() {
+        let grammar = "Prelude {} Skip {} Tokens { Num -> \"[0-9]+\"; } NotTerminals { S {} {} -> n:Num (Num {println!(\"{}\", n.ident);})*; }";
+        let not_terminals = desugar_groups(resolve_labels(parse_parser_description(grammar).unwrap())).not_terminal;
+        assert_eq!(vec!["Num()", "S_1_star(Num0_ident.clone())"], members(&not_terminals[0], 0));
+        assert_eq!(vec!["Num()", "{println!(\"{}\", label_n_ident);}", "S_1_star(label_n_ident.clone())"], members(&not_terminals[1], 0));
+    }
+
+    #[test]
+    fn brackets_after_call_without_args_are_group() {
+        let not_terminals = desugar("S {} {} -> E (Plus | Num)?; E {} {} -> Lb Rb;");
+        assert_eq!(vec!["E()", "S_1_opt()"], members(&not_terminals[0], 0));
+        assert_eq!(3, not_terminals[2].rules.len());
+    }
+}
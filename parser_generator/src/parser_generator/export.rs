@@ -0,0 +1,224 @@
+use crate::parser_generator::first_follow::{GrammarTables, NotTerminalSets, PredictionRow};
+
+/// Format of [`GrammarTables::export`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    /// One row per not terminal with its sets and the rules it predicts at every token.
+    Csv,
+    Markdown,
+    Html,
+}
+
+impl GrammarTables {
+    /// Renders the sets and the prediction table. Rows follow the declaration order of not terminals,
+    /// columns that of tokens with `Eof` last, so exports of two grammar revisions diff line by line.
+    /// Cells show the text of the predicted rule, rules of an LL(1) conflict are joined with ` / `.
+    pub fn export(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Json => self.to_json(),
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Markdown => self.to_markdown(),
+            ExportFormat::Html => self.to_html(),
+        }
+    }
+
+    fn rows(&self) -> impl Iterator<Item = (&NotTerminalSets, &PredictionRow)> {
+        self.sets.sets.iter().zip(&self.prediction.rows)
+    }
+
+    fn to_json(&self) -> String {
+        let mut answer = String::from("{\n");
+        answer.push_str(&format!("  \"tokens\": {},\n", json_list(&self.prediction.tokens)));
+        answer.push_str("  \"not_terminals\": [\n");
+        let not_terminals = self
+            .rows()
+            .map(|(sets, row)| {
+                let predictions = row
+                    .cells
+                    .iter()
+                    .zip(&self.prediction.tokens)
+                    .filter(|(cell, _)| !cell.is_empty())
+                    .map(|(cell, token)| {
+                        let rules = cell.iter().map(usize::to_string).collect::<Vec<String>>().join(", ");
+                        format!("{}: [{rules}]", json_string(token))
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!(
+                    "    {{\n      \"name\": {},\n      \"nullable\": {},\n      \"first\": {},\n      \"follow\": {},\n      \"rules\": {},\n      \"predictions\": {{{predictions}}}\n    }}",
+                    json_string(&sets.not_terminal),
+                    sets.nullable,
+                    json_list(&sets.first),
+                    json_list(&sets.follow),
+                    json_list(&row.rules),
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",\n");
+        answer.push_str(&not_terminals);
+        answer.push_str("\n  ]\n}\n");
+        answer
+    }
+
+    fn to_csv(&self) -> String {
+        let mut header = vec!["not_terminal", "nullable", "first", "follow"];
+        header.extend(self.prediction.tokens.iter().map(String::as_str));
+        let mut answer = csv_line(header.into_iter().map(str::to_string));
+        for (sets, row) in self.rows() {
+            let fields = [
+                sets.not_terminal.clone(),
+                sets.nullable.to_string(),
+                sets.first.join(" "),
+                sets.follow.join(" "),
+            ];
+            answer.push_str(&csv_line(fields.into_iter().chain(row.cells.iter().map(|cell| cell_text(row, cell)))));
+        }
+        answer
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut answer = String::from("| Not terminal | Nullable | FIRST | FOLLOW |\n| --- | --- | --- | --- |\n");
+        for sets in &self.sets.sets {
+            let nullable = if sets.nullable { "yes" } else { "no" };
+            answer.push_str(&markdown_line([
+                sets.not_terminal.clone(),
+                nullable.to_string(),
+                sets.first.join(", "),
+                sets.follow.join(", "),
+            ]));
+        }
+
+        answer.push('\n');
+        answer.push_str(&markdown_line(std::iter::once(String::new()).chain(self.prediction.tokens.iter().cloned())));
+        answer.push_str(&format!("|{}\n", " --- |".repeat(self.prediction.tokens.len() + 1)));
+        for row in &self.prediction.rows {
+            let cells = row.cells.iter().map(|cell| cell_text(row, cell));
+            answer.push_str(&markdown_line(std::iter::once(row.not_terminal.clone()).chain(cells)));
+        }
+        answer
+    }
+
+    fn to_html(&self) -> String {
+        let mut answer = String::from("<table class=\"first-follow\">\n");
+        answer.push_str("<tr><th>Not terminal</th><th>Nullable</th><th>FIRST</th><th>FOLLOW</th></tr>\n");
+        for sets in &self.sets.sets {
+            let nullable = if sets.nullable { "yes" } else { "no" };
+            answer.push_str(&format!(
+                "<tr><th>{}</th><td>{nullable}</td><td>{}</td><td>{}</td></tr>\n",
+                html_escape(&sets.not_terminal),
+                html_escape(&sets.first.join(", ")),
+                html_escape(&sets.follow.join(", ")),
+            ));
+        }
+        answer.push_str("</table>\n<table class=\"prediction\">\n<tr><th></th>");
+        for token in &self.prediction.tokens {
+            answer.push_str(&format!("<th>{}</th>", html_escape(token)));
+        }
+        answer.push_str("</tr>\n");
+        for row in &self.prediction.rows {
+            answer.push_str(&format!("<tr><th>{}</th>", html_escape(&row.not_terminal)));
+            for cell in &row.cells {
+                let class = if cell.len() > 1 { " class=\"conflict\"" } else { "" };
+                answer.push_str(&format!("<td{class}>{}</td>", html_escape(&cell_text(row, cell))));
+            }
+            answer.push_str("</tr>\n");
+        }
+        answer.push_str("</table>\n");
+        answer
+    }
+}
+
+fn cell_text(row: &PredictionRow, cell: &[usize]) -> String {
+    cell.iter().map(|rule| row.rules[*rule].as_str()).collect::<Vec<&str>>().join(" / ")
+}
+
+fn json_string(s: &str) -> String {
+    let mut answer = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => answer.push_str("\\\""),
+            '\\' => answer.push_str("\\\\"),
+            '\n' => answer.push_str("\\n"),
+            c if c.is_control() => answer.push_str(&format!("\\u{:04x}", c as u32)),
+            c => answer.push(c),
+        }
+    }
+    answer.push('"');
+    answer
+}
+
+fn json_list(items: &[String]) -> String {
+    format!("[{}]", items.iter().map(|item| json_string(item)).collect::<Vec<String>>().join(", "))
+}
+
+fn csv_line(fields: impl Iterator<Item = String>) -> String {
+    let fields = fields
+        .map(|field| match field.contains([',', '"', '\n']) {
+            true => format!("\"{}\"", field.replace('"', "\"\"")),
+            false => field,
+        })
+        .collect::<Vec<String>>();
+    format!("{}\n", fields.join(","))
+}
+
+fn markdown_line(cells: impl IntoIterator<Item = String>) -> String {
+    let cells = cells.into_iter().map(|cell| cell.replace('|', "\\|")).collect::<Vec<String>>();
+    format!("| {} |\n", cells.join(" | "))
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_generator::first_follow::GrammarTables;
+    use crate::parser_generator::parser_description::parse_parser_description;
+    use crate::parser_generator::parser_generator::{get_first_follow, get_prediction_table};
+    use super::ExportFormat;
+
+    const GRAMMAR: &str = r#"
+Prelude {}
+Skip { " "; }
+Tokens {
+    Num -> "[0-9]+";
+    Plus -> "\\+";
+}
+NotTerminals {
+    S {} {} -> Num Ex;
+    Ex {} {} -> Plus Num Ex | ;
+}
+"#;
+
+    fn tables(grammar: &str) -> GrammarTables {
+        let description = parse_parser_description(grammar).unwrap();
+        GrammarTables { sets: get_first_follow(&description), prediction: get_prediction_table(&description) }
+    }
+
+    #[test]
+    fn json_lists_sets_and_predictions() {
+        let json = tables(GRAMMAR).export(ExportFormat::Json);
+        assert!(json.starts_with("{\n  \"tokens\": [\"Num\", \"Plus\", \"Eof\"],\n"));
+        assert!(json.contains("\"name\": \"Ex\",\n      \"nullable\": true,\n      \"first\": [\"Plus\", \"ε\"],\n      \"follow\": [\"Eof\"],\n      \"rules\": [\"Plus Num Ex\", \"ε\"],\n      \"predictions\": {\"Plus\": [0], \"Eof\": [1]}"));
+    }
+
+    #[test]
+    fn csv_and_markdown_have_a_row_per_not_terminal() {
+        let tables = tables(GRAMMAR);
+        assert_eq!(
+            "not_terminal,nullable,first,follow,Num,Plus,Eof\nS,false,Num,Eof,Num Ex,,\nEx,true,Plus ε,Eof,,Plus Num Ex,ε\n",
+            tables.export(ExportFormat::Csv),
+        );
+        let markdown = tables.export(ExportFormat::Markdown);
+        assert!(markdown.contains("| Ex | yes | Plus, ε | Eof |\n"));
+        assert!(markdown.contains("|  | Num | Plus | Eof |\n| --- | --- | --- | --- |\n| S | Num Ex |  |  |\n"));
+    }
+
+    #[test]
+    fn html_marks_conflicts() {
+        let grammar = GRAMMAR.replace("Ex {} {} -> Plus Num Ex | ;", "Ex {} {} -> Plus Num Ex | Plus | ;");
+        let html = tables(&grammar).export(ExportFormat::Html);
+        assert!(html.contains("<tr><th>Ex</th><td></td><td class=\"conflict\">Plus Num Ex / Plus</td><td>ε</td></tr>"));
+    }
+}
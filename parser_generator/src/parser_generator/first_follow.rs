@@ -0,0 +1,54 @@
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NotTerminalSets {
+    pub not_terminal: String,
+    /// The not terminal derives the empty string.
+    pub nullable: bool,
+    pub first: Vec<String>,
+    pub follow: Vec<String>,
+}
+
+/// FIRST and FOLLOW sets of a grammar, tokens are sorted and `ε` marks the empty string.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FirstFollow {
+    pub sets: Vec<NotTerminalSets>,
+}
+
+impl Display for FirstFollow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for NotTerminalSets { not_terminal, first, .. } in &self.sets {
+            writeln!(f, "FIRST({not_terminal}) = {{ {} }}", first.join(", "))?;
+        }
+        writeln!(f)?;
+        for NotTerminalSets { not_terminal, follow, .. } in &self.sets {
+            writeln!(f, "FOLLOW({not_terminal}) = {{ {} }}", follow.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Rules of every not terminal predicted at every lookahead token.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PredictionTable {
+    /// Columns, the tokens in declaration order and `Eof`.
+    pub tokens: Vec<String>,
+    pub rows: Vec<PredictionRow>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PredictionRow {
+    pub not_terminal: String,
+    /// Text of the rules, `ε` for the empty one.
+    pub rules: Vec<String>,
+    /// Indices of the rules predicted at every column, empty where the token is an error
+    /// and with more than one rule where the grammar has an LL(1) conflict.
+    pub cells: Vec<Vec<usize>>,
+}
+
+/// FIRST, FOLLOW and nullable sets of a grammar with its prediction table, see [`GrammarTables::export`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GrammarTables {
+    pub sets: FirstFollow,
+    pub prediction: PredictionTable,
+}
@@ -0,0 +1,44 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::parser_generator::ll1_conflict::ConflictReport;
+use crate::parser_generator::parser_description::GrammarError;
+use crate::parser_generator::validation::ValidationReport;
+
+#[derive(Debug)]
+pub enum GeneratorError {
+    Grammar(GrammarError),
+    /// Undefined or duplicate names and wrong references found in a well-formed grammar.
+    Validation(ValidationReport),
+    Conflicts(ConflictReport),
+}
+
+impl Display for GeneratorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneratorError::Grammar(err) => Display::fmt(err, f),
+            GeneratorError::Validation(report) => Display::fmt(report, f),
+            GeneratorError::Conflicts(report) => Display::fmt(report, f),
+        }
+    }
+}
+
+impl Error for GeneratorError {}
+
+impl From<GrammarError> for GeneratorError {
+    fn from(err: GrammarError) -> Self {
+        GeneratorError::Grammar(err)
+    }
+}
+
+impl From<ValidationReport> for GeneratorError {
+    fn from(report: ValidationReport) -> Self {
+        GeneratorError::Validation(report)
+    }
+}
+
+impl From<ConflictReport> for GeneratorError {
+    fn from(report: ConflictReport) -> Self {
+        GeneratorError::Conflicts(report)
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::{HashMap, HashSet};
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::parser_generator::action_code::{identifiers, member_prefix, readable_results};
+use crate::parser_generator::parser_description::{ParserDescription, Rule, RuleMember, RuleToken, Typed};
+
+lazy_static! {
+    static ref LABELLED_REGEX: Regex = Regex::new("([A-Za-z_][A-Za-z0-9_]*)\\s*\\.\\s*([A-Za-z_][A-Za-z0-9_]*)").unwrap();
+    static ref BINDING_REGEX: Regex = Regex::new(r"\blet\s+([^=;]*)|\bfor\s+([^;{]*?)\s+in\b|\|([^|]*)\|").unwrap();
+}
+
+/// Replaces `label.result` in actions and call arguments with the positional name of the
+/// result of the labelled member, e.g. `lhs.res` with `E0_res` for `lhs:E`, so later rewrites
+/// renumber them like any other reference.
+///
+/// Labels are visible in the whole alternative and in its groups, where labels of the
+/// group's own members shadow them. Inside a group a label of an enclosing alternative is
+/// replaced with the argument the group's helper gets the result in, e.g. `label_lhs_res`,
+/// so it isn't mistaken for a member of the group at the same position. In the code they shadow locals of the same name, unless
+/// the code binds the name itself with `let`, `for` or as a closure parameter, then the label
+/// isn't replaced there. `label.result` of an unknown label or result is left as is.
+pub fn resolve_labels(mut parser_description: ParserDescription) -> ParserDescription {
+    let results = readable_results(&parser_description);
+    for not_term in &mut parser_description.not_terminal {
+        for rule in &mut not_term.rules {
+            resolve_rule(rule, &HashMap::new(), &results);
+        }
+    }
+    parser_description
+}
+
+/// Name of the member of every label and the prefix its results are replaced with, as `label -> (name, prefix)`.
+type Labels = HashMap<String, (String, String)>;
+
+/// Prefix of the arguments of a group helper holding results of the member labelled `label`
+/// in an enclosing alternative, e.g. `label_lhs_` for `label_lhs_res`.
+pub fn label_arg_prefix(label: &str) -> String {
+    format!("label_{label}_")
+}
+
+fn resolve_rule(rule: &mut Rule, outer: &Labels, results: &HashMap<String, Vec<Typed>>) {
+    let mut labels = outer.clone();
+    for (i, member) in rule.members.iter().enumerate() {
+        if let RuleMember::RuleCall(RuleToken { name, annotation, .. }) = member {
+            if let Some(label) = &annotation.label {
+                labels.insert(label.clone(), (name.clone(), member_prefix(name, i)));
+            }
+        }
+    }
+    if labels.is_empty() {
+        return;
+    }
+
+    for member in &mut rule.members {
+        match member {
+            RuleMember::RuleCall(token) => token.args = replace_labels(&token.args, &labels, results),
+            RuleMember::Command(code) => *code = replace_labels(code, &labels, results),
+            RuleMember::Group(group) => {
+                let threaded: Labels = labels
+                    .iter()
+                    .map(|(label, (name, _))| (label.clone(), (name.clone(), label_arg_prefix(label))))
+                    .collect();
+                for alternative in &mut group.alternatives {
+                    resolve_rule(alternative, &threaded, results);
+                }
+            }
+        }
+    }
+}
+
+fn replace_labels(code: &str, labels: &Labels, results: &HashMap<String, Vec<Typed>>) -> String {
+    let bound = bound_names(code);
+    LABELLED_REGEX
+        .replace_all(code, |caps: &Captures| {
+            let whole = caps.get(0).unwrap();
+            let member = labels.get(&caps[1]).filter(|(name, _)| {
+                results.get(name).is_some_and(|results| results.iter().any(|typed| typed.name == caps[2]))
+            });
+            match member {
+                Some((_, prefix)) if !is_field_access(code, whole.start()) && !bound.contains(&caps[1]) =>
+                    format!("{prefix}{}", &caps[2]),
+                _ => whole.as_str().to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// `label.result` candidates in the code as offset, label and result, `x.label.result` and
+/// names the code binds itself are left out.
+pub fn label_references(code: &str) -> impl Iterator<Item = (usize, &str, &str)> {
+    let bound = bound_names(code);
+    LABELLED_REGEX
+        .captures_iter(code)
+        .map(|caps| (caps.get(0).unwrap().start(), caps.get(1).unwrap().as_str(), caps.get(2).unwrap().as_str()))
+        .filter(move |(start, label, _)| !is_field_access(code, *start) && !bound.contains(label))
+}
+
+/// Names bound by `let` and `for` patterns and closure parameters in the code.
+fn bound_names(code: &str) -> HashSet<&str> {
+    BINDING_REGEX
+        .captures_iter(code)
+        .flat_map(|caps| caps.iter().skip(1).flatten().flat_map(|pattern| identifiers(pattern.as_str())).collect::<Vec<&str>>())
+        .collect()
+}
+
+/// Whether the identifier at `start` is a field of the value before it, but not the end of a range.
+fn is_field_access(code: &str, start: usize) -> bool {
+    let before = code[..start].trim_end();
+    before.ends_with('.') && !before.ends_with("..")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_generator::parser_description::{parse_parser_description, NotTerminal, Rule, RuleMember};
+    use super::resolve_labels;
+
+    fn resolve(not_terminals: &str) -> Vec<NotTerminal> {
+        let grammar = format!(
+            "Prelude {{}} Skip {{}} Tokens {{ Num -> \"[0-9]+\"; Plus -> \"\\\\+\"; }} NotTerminals {{ {not_terminals} }}"
+        );
+        resolve_labels(parse_parser_description(&grammar).unwrap()).not_terminal
+    }
+
+    fn commands(rule: &Rule) -> Vec<String> {
+        rule.members
+            .iter()
+            .filter_map(|member| match member {
+                RuleMember::RuleCall(token) => Some(token.args.clone()),
+                RuleMember::Command(code) => Some(code.clone()),
+                RuleMember::Group(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn labels_become_positional_prefixes() {
+        let not_terminals = resolve(
+            "S {} {res # i64} -> E {res = E0_res;}; \
+             E {} {res # i64} -> lhs:E Plus rhs:T {res = lhs.res + rhs . res; let _ = (rhs.span, x.lhs.res, 0..lhs.res);} | T {res = T0_res;}; \
+             T {acc # i64} {res # i64} -> n:Num T(n.ident.len() as i64) {res = other.res;};"
+        );
+        assert_eq!(
+            vec!["()", "()", "()", "res = E0_res + T2_res; let _ = (T2_span, x.lhs.res, 0..E0_res);"],
+            commands(&not_terminals[1].rules[0]),
+        );
+        assert_eq!(vec!["()", "(Num0_ident.len() as i64)", "res = other.res;"], commands(&not_terminals[2].rules[0]));
+    }
+
+    #[test]
+    fn names_bound_in_the_code_are_not_labels() {
+        let not_terminals = resolve(
+            "S {} {res # i64} -> n:Num Plus e:Num {res = n.ident.len() as i64 + e.len() as i64;} \
+             {res += vec![e.ident].iter().map(|n| n.len()).sum::<usize>() as i64;} {let e = e.ident;};"
+        );
+        assert_eq!(
+            vec![
+                "()", "()", "()",
+                "res = Num0_ident.len() as i64 + e.len() as i64;",
+                "res += vec![Num2_ident].iter().map(|n| n.len()).sum::<usize>() as i64;",
+                "let e = e.ident;",
+            ],
+            commands(&not_terminals[0].rules[0]),
+        );
+    }
+
+    #[test]
+    fn group_labels_shadow_outer_labels() {
+        let not_terminals = resolve(
+            "S {} {res # i64} -> n:Num {res = n.span.start as i64;} (Plus n:Num {res += n.span.start as i64;} | Plus {res += n.span.start as i64;})*;"
+        );
+        let RuleMember::Group(group) = &not_terminals[0].rules[0].members[2] else { panic!() };
+        assert_eq!(vec!["()", "()", "res += Num1_span.start as i64;"], commands(&group.alternatives[0]));
+        assert_eq!(vec!["()", "res += label_n_span.start as i64;"], commands(&group.alternatives[1]));
+    }
+}
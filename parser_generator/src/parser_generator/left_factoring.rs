@@ -0,0 +1,356 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::parser_generator::action_code::{
+    identifiers, member_prefix, member_results, rename_prefixes, renamed_prefix, rewrite_member_identifiers, used_identifiers, with_span,
+};
+use crate::parser_generator::names::Names;
+use crate::parser_generator::parser_description::{Annotation, NotTerminal, ParserDescription, Rule, RuleMember, RuleToken, Typed};
+
+/// Position in one alternative while its common prefix with the others is collected.
+struct Cursor<'a> {
+    members: &'a [RuleMember],
+    next: usize,
+    /// Commands of the prefix which are run at the start of the suffix instead.
+    moved: Vec<RuleMember>,
+    /// Calls of the prefix as `(name, index in the alternative, index in the prefix)`.
+    calls: Vec<(String, usize, usize)>,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(rule: &'a Rule) -> Cursor<'a> {
+        Cursor { members: &rule.members, next: 0, moved: Vec::new(), calls: Vec::new() }
+    }
+
+    /// Commands before the next call and the position of the call.
+    fn pending(&self) -> (&'a [RuleMember], Option<usize>) {
+        let call = self.members[self.next..]
+            .iter()
+            .position(|member| !matches!(member, RuleMember::Command(_)))
+            .map(|i| i + self.next);
+        let end = call.unwrap_or(self.members.len());
+        (&self.members[self.next..end], call)
+    }
+
+    /// Renames references to calls of the prefix to their position in the prefix.
+    fn normalize(&self, code: &str) -> String {
+        let renames: Vec<(String, String)> = self.calls
+            .iter()
+            .map(|(name, from, to)| (member_prefix(name, *from), member_prefix(name, *to)))
+            .collect();
+        rename_prefixes(code, &renames)
+    }
+
+    fn normalize_member(&self, member: &RuleMember) -> RuleMember {
+        match member {
+            RuleMember::RuleCall(token) =>
+                RuleMember::RuleCall(RuleToken { args: self.normalize(&token.args), ..token.clone() }),
+            RuleMember::Command(code) => RuleMember::Command(self.normalize(code)),
+            RuleMember::Group(group) => RuleMember::Group(group.clone()),
+        }
+    }
+
+    /// Members left after the prefix, renumbered from zero, with references to
+    /// calls of the prefix renamed to arguments of `helper`.
+    fn suffix(&self, helper: &str) -> Rule {
+        let mut members = self.moved.clone();
+        members.extend(self.members[self.next..].iter().cloned());
+
+        let shift = self.moved.len();
+        let mut renames: Vec<(String, String)> = self.calls
+            .iter()
+            .map(|(name, from, to)| (member_prefix(name, *from), prefix_arg(helper, name, *to)))
+            .collect();
+        renames.extend(
+            self.members
+                .iter()
+                .enumerate()
+                .skip(self.next)
+                .filter_map(|(i, member)| match member {
+                    RuleMember::RuleCall(RuleToken { name, .. }) =>
+                        Some((member_prefix(name, i), member_prefix(name, i - self.next + shift))),
+                    _ => None,
+                })
+                .filter(|(from, to)| from != to),
+        );
+        rewrite_member_identifiers(&mut members, &|ident| renamed_prefix(ident, &renames));
+        Rule { members }
+    }
+}
+
+/// Name prefix of the argument of `helper` holding results of a call of the prefix.
+fn prefix_arg(helper: &str, name: &str, index: usize) -> String {
+    format!("{helper}_{}", member_prefix(name, index))
+}
+
+/// Name and arguments of the first call of an alternative.
+type CallKey<'a> = Option<(&'a str, String)>;
+
+fn first_call(rule: &Rule) -> CallKey<'_> {
+    rule.members.iter().find_map(|member| match member {
+        RuleMember::RuleCall(RuleToken { name, args, .. }) => Some((name.as_str(), args.replace(char::is_whitespace, ""))),
+        _ => None,
+    })
+}
+
+struct Factorer {
+    /// Results of the members, see [`member_results`].
+    returns: HashMap<String, Vec<Typed>>,
+    span_type: String,
+    roots: HashMap<String, String>,
+    names: HashSet<String>,
+    queue: VecDeque<NotTerminal>,
+}
+
+/// Extracts common prefixes of alternatives into helper not terminals named
+/// `{NotTerminal}_{n}_fact`, e.g. `S -> If E Then S | If E Then S Else S`
+/// becomes `S -> If E Then S S_1_fact`, `S_1_fact -> ε | Else S`.
+///
+/// Commands inside the prefix are moved to the start of every suffix unless a
+/// later call of the prefix reads something they assign, then they have to be
+/// the same in every alternative and stay in the prefix. Results of the prefix
+/// calls, arguments and already initialized returns of the not terminal are
+/// passed to the helper, so positional references like `E1_res` of the suffix
+/// are renumbered and keep pointing to the same members.
+pub fn factor_alternatives(mut parser_description: ParserDescription) -> ParserDescription {
+    let returns = member_results(&parser_description);
+    let span_type = Names::new(&parser_description.options).span;
+    let not_terminal = std::mem::take(&mut parser_description.not_terminal);
+    let names = returns.keys().cloned().collect();
+
+    let mut factorer = Factorer { returns, span_type, roots: HashMap::new(), names, queue: not_terminal.into() };
+
+    let mut factored = Vec::new();
+    while let Some(mut not_term) = factorer.queue.pop_front() {
+        not_term.rules = factorer.factor_rules(&not_term);
+        factored.push(not_term);
+    }
+
+    ParserDescription { not_terminal: factored, ..parser_description }
+}
+
+impl Factorer {
+    fn factor_rules(&mut self, not_term: &NotTerminal) -> Vec<Rule> {
+        let mut groups: Vec<(CallKey, Vec<&Rule>)> = Vec::new();
+        for rule in &not_term.rules {
+            let key = first_call(rule);
+            match groups.iter_mut().find(|(k, _)| key.is_some() && *k == key) {
+                Some((_, rules)) => rules.push(rule),
+                None => groups.push((key, vec![rule])),
+            }
+        }
+
+        groups
+            .into_iter()
+            .flat_map(|(_, rules)| match rules.as_slice() {
+                [rule] => vec![(*rule).clone()],
+                _ => self.factor_group(not_term, &rules),
+            })
+            .collect()
+    }
+
+    /// Factors alternatives starting with the same call, they are returned unchanged when
+    /// nothing can be shared, e.g. the first calls read what different commands before them assign.
+    fn factor_group(&mut self, not_term: &NotTerminal, rules: &[&Rule]) -> Vec<Rule> {
+        let mut cursors: Vec<Cursor> = rules.iter().map(|rule| Cursor::new(rule)).collect();
+        let mut prefix: Vec<RuleMember> = Vec::new();
+
+        loop {
+            let pending: Vec<(&[RuleMember], Option<usize>)> = cursors.iter().map(Cursor::pending).collect();
+            let Some(calls) = pending.iter().map(|(_, call)| *call).collect::<Option<Vec<usize>>>() else {
+                break;
+            };
+            let normalized: Vec<RuleMember> = cursors
+                .iter()
+                .zip(&calls)
+                .map(|(cursor, &call)| cursor.normalize_member(&cursor.members[call]))
+                .collect();
+            let RuleMember::RuleCall(RuleToken { name, args, .. }) = &normalized[0] else {
+                break;
+            };
+            let same_call = normalized.iter().all(|member| matches!(
+                member,
+                RuleMember::RuleCall(token) if &token.name == name && token.args.replace(char::is_whitespace, "") == args.replace(char::is_whitespace, "")
+            ));
+            if !same_call {
+                break;
+            }
+
+            let read: HashSet<&str> = identifiers(args).collect();
+            let assigns_read = pending.iter().any(|(commands, _)| {
+                used_identifiers(commands).iter().any(|ident| read.contains(ident.as_str()))
+            });
+            if assigns_read {
+                let kept: Vec<Vec<RuleMember>> = cursors
+                    .iter()
+                    .zip(&pending)
+                    .map(|(cursor, (commands, _))| commands.iter().map(|c| cursor.normalize_member(c)).collect())
+                    .collect();
+                let same_commands = kept.iter().all(|commands| {
+                    commands.len() == kept[0].len() && commands.iter().zip(&kept[0]).all(|pair| matches!(
+                        pair,
+                        (RuleMember::Command(a), RuleMember::Command(b)) if a == b
+                    ))
+                });
+                if !same_commands {
+                    break;
+                }
+                prefix.extend(kept[0].iter().cloned());
+            } else {
+                for (cursor, (commands, _)) in cursors.iter_mut().zip(&pending) {
+                    cursor.moved.extend(commands.iter().cloned());
+                }
+            }
+
+            let index = prefix.len();
+            let name = name.clone();
+            prefix.push(normalized[0].clone());
+            for (cursor, &call) in cursors.iter_mut().zip(&calls) {
+                cursor.calls.push((name.clone(), call, index));
+                cursor.next = call + 1;
+            }
+        }
+        if prefix.is_empty() {
+            return rules.iter().map(|rule| (*rule).clone()).collect();
+        }
+
+        let helper = self.next_name(&not_term.name);
+        let suffixes: Vec<Rule> = cursors.iter().map(|cursor| cursor.suffix(&helper)).collect();
+        let used = used_identifiers(&suffixes.iter().flat_map(|rule| rule.members.clone()).collect::<Vec<RuleMember>>());
+        let initialized = used_identifiers(&prefix);
+
+        let mut args: Vec<Typed> = Vec::new();
+        let mut call_args: Vec<String> = Vec::new();
+        for typed in &not_term.returns {
+            let passed = not_term.args.iter().any(|arg| arg.name == typed.name) || initialized.contains(&typed.name);
+            if passed && used.contains(&typed.name) {
+                args.push(typed.clone());
+                call_args.push(typed.name.clone());
+            }
+        }
+        for typed in &not_term.args {
+            if used.contains(&typed.name) && !args.iter().any(|t| t.name == typed.name) {
+                args.push(typed.clone());
+                call_args.push(format!("{}.clone()", typed.name));
+            }
+        }
+        for (index, member) in prefix.iter().enumerate() {
+            let RuleMember::RuleCall(RuleToken { name, .. }) = member else {
+                continue;
+            };
+            for Typed { name: attr, ty } in self.returns.get(name).into_iter().flatten() {
+                let arg = format!("{}{attr}", prefix_arg(&helper, name, index));
+                if used.contains(&arg) {
+                    args.push(Typed { name: arg, ty: ty.clone() });
+                    call_args.push(format!("{}{attr}.clone()", member_prefix(name, index)));
+                }
+            }
+        }
+
+        let index = prefix.len();
+        let call = RuleToken { name: helper.clone(), args: format!("({})", call_args.join(", ")), annotation: Annotation::default(), position: 0 };
+        prefix.push(RuleMember::RuleCall(call));
+        if !not_term.returns.is_empty() {
+            let helper_prefix = member_prefix(&helper, index);
+            let write_back = not_term.returns
+                .iter()
+                .map(|Typed { name, .. }| format!("{name} = {helper_prefix}{name};"))
+                .collect::<Vec<String>>()
+                .join(" ");
+            prefix.push(RuleMember::Command(write_back));
+        }
+
+        let root = self.roots.get(&not_term.name).cloned().unwrap_or(not_term.name.clone());
+        self.roots.insert(helper.clone(), root);
+        self.returns.insert(helper.clone(), with_span(&not_term.returns, &self.span_type));
+        self.queue.push_back(NotTerminal {
+            name: helper,
+            args,
+            returns: not_term.returns.clone(),
+            rules: suffixes,
+            position: not_term.position,
+        });
+
+        vec![Rule { members: prefix }]
+    }
+
+    fn next_name(&mut self, parent: &str) -> String {
+        let root = self.roots.get(parent).map_or(parent, String::as_str);
+        let name = (1..)
+            .map(|n| format!("{root}_{n}_fact"))
+            .find(|name| !self.names.contains(name))
+            .unwrap();
+        self.names.insert(name.clone());
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_generator::parser_description::{parse_parser_description, NotTerminal, RuleMember, RuleToken};
+    use super::factor_alternatives;
+
+    fn factor(not_terminals: &str) -> Vec<NotTerminal> {
+        let grammar = format!(
+            "Prelude {{}} Skip {{}} Tokens {{ If -> \"if\"; Then -> \"then\"; Else -> \"else\"; Num -> \"[0-9]+\"; Plus -> \"\\\\+\"; }} NotTerminals {{ {not_terminals} }}"
+        );
+        factor_alternatives(parse_parser_description(&grammar).unwrap()).not_terminal
+    }
+
+    fn members(not_term: &NotTerminal, rule: usize) -> Vec<String> {
+        not_term.rules[rule].members
+            .iter()
+            .map(|member| match member {
+                RuleMember::RuleCall(RuleToken { name, args, .. }) => format!("{name}{args}"),
+                RuleMember::Command(code) => format!("{{{code}}}"),
+                RuleMember::Group(_) => "group".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dangling_else_is_factored() {
+        let not_terminals = factor(
+            "S {} {res # i64} -> If Num Then S {res = S3_res;} | If Num Then S Else S {res = S3_res + S5_res;} | Num {res = 1;};"
+        );
+        assert_eq!(2, not_terminals[0].rules.len());
+        assert_eq!(
+            vec!["If()", "Num()", "Then()", "S()", "S_1_fact(S3_res.clone())", "{res = S_1_fact4_res;}"],
+            members(&not_terminals[0], 0),
+        );
+
+        let helper = &not_terminals[1];
+        assert_eq!("S_1_fact", helper.name);
+        assert_eq!(vec!["S_1_fact_S3_res"], helper.args.iter().map(|t| t.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(vec!["{res = S_1_fact_S3_res;}"], members(helper, 0));
+        assert_eq!(vec!["Else()", "S()", "{res = S_1_fact_S3_res + S1_res;}"], members(helper, 1));
+    }
+
+    #[test]
+    fn commands_in_prefix_are_moved_or_kept() {
+        let not_terminals = factor(
+            "E {} {res # i64} -> Num {res = 0;} Acc(res) {res = Acc2_res;} Plus | Num {res = 0;} Acc(res) {res = Acc2_res + 1;}; \
+             Acc {acc # i64} {res # i64} -> Num {res = acc;} | {println!(\"a\");} Num {res = Num1_ident.len() as i64;};"
+        );
+        assert_eq!(
+            vec!["Num()", "{res = 0;}", "Acc(res)", "E_1_fact(res, Acc2_res.clone())", "{res = E_1_fact3_res;}"],
+            members(&not_terminals[0], 0),
+        );
+        assert_eq!(vec!["{res = E_1_fact_Acc2_res;}", "Plus()"], members(&not_terminals[2], 0));
+        assert_eq!(
+            vec!["Num()", "Acc_1_fact(acc.clone(), Num0_ident.clone())", "{res = Acc_1_fact1_res;}"],
+            members(&not_terminals[1], 0),
+        );
+        assert_eq!(vec!["{println!(\"a\");}", "{res = Acc_1_fact_Num0_ident.len() as i64;}"], members(&not_terminals[3], 1));
+    }
+
+    #[test]
+    fn calls_reading_different_commands_are_not_factored() {
+        let not_terminals = factor(
+            "X {} {x # i64} -> {x = 1;} Y(x) Plus | {x = 2;} Y(x) Num; \
+             Y {a # i64} {} -> Then;"
+        );
+        assert_eq!(2, not_terminals.len());
+        assert_eq!(vec!["{x = 1;}", "Y(x)", "Plus()"], members(&not_terminals[0], 0));
+        assert_eq!(vec!["{x = 2;}", "Y(x)", "Num()"], members(&not_terminals[0], 1));
+    }
+}
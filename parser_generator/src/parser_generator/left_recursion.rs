@@ -0,0 +1,471 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use crate::parser_generator::action_code::{member_prefix, moved_prefixes, renamed_prefix, rewrite_member_identifiers, used_identifiers};
+use crate::parser_generator::names::Names;
+use crate::parser_generator::parser_description::{Annotation, NotTerminal, ParserDescription, Rule, RuleMember, RuleToken, Typed};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LeftRecursionRewrite {
+    /// `A -> A α | β` became `A -> β tail`, `tail -> α tail | ε`.
+    Direct { not_terminal: String, tail: String, alternatives: usize },
+    /// Alternatives of `not_terminal` starting with `inlined` were replaced by the
+    /// left recursive alternatives of `inlined` and a call of `base` with the others.
+    Substituted { not_terminal: String, inlined: String, base: Option<String> },
+    Unsupported { not_terminal: String, reason: String },
+}
+
+impl Display for LeftRecursionRewrite {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LeftRecursionRewrite::Direct { not_terminal, tail, alternatives } => write!(
+                f,
+                "`{not_terminal}`: direct left recursion in {alternatives} alternative(s) moved to right recursive `{tail}`"
+            ),
+            LeftRecursionRewrite::Substituted { not_terminal, inlined, base } => {
+                write!(f, "`{not_terminal}`: indirect left recursion through `{inlined}` resolved by inlining its alternatives")?;
+                if let Some(base) = base {
+                    write!(f, ", the other alternatives are called as `{base}`")?;
+                }
+                Ok(())
+            }
+            LeftRecursionRewrite::Unsupported { not_terminal, reason } =>
+                write!(f, "`{not_terminal}`: left recursion is not rewritten, {reason}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct LeftRecursionReport {
+    pub rewrites: Vec<LeftRecursionRewrite>,
+}
+
+impl LeftRecursionReport {
+    pub fn is_empty(&self) -> bool {
+        self.rewrites.is_empty()
+    }
+}
+
+impl Display for LeftRecursionReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for rewrite in &self.rewrites {
+            writeln!(f, "{rewrite}")?;
+        }
+        Ok(())
+    }
+}
+
+struct Eliminator {
+    not_terminals: Vec<NotTerminal>,
+    span_type: String,
+    bases: HashMap<String, String>,
+    rewrites: Vec<LeftRecursionRewrite>,
+}
+
+/// Rewrites direct and indirect left recursion into right recursion.
+///
+/// `A -> A α {..A0_res..} | β` becomes `A -> β A_tail(res)` and
+/// `A_tail {A0_res # T} {res # T} -> α {..A0_res..} A_tail(res) | {res = A0_res;}`,
+/// so actions of `α` keep reading the result of the left `A` from `A0_res` and
+/// left associative semantics is preserved. `A0_span` is passed the same way
+/// when the actions read it.
+///
+/// Not terminals of one left recursive cycle are ordered by declaration and
+/// alternatives starting with an earlier one are replaced by its left recursive
+/// alternatives, while the rest of its alternatives are moved to `{Name}_base`.
+pub fn eliminate_left_recursion(mut parser_description: ParserDescription) -> (ParserDescription, LeftRecursionReport) {
+    let not_terminal = std::mem::take(&mut parser_description.not_terminal);
+
+    let cycles = get_left_recursive_cycles(&not_terminal);
+    let span_type = Names::new(&parser_description.options).span;
+    let mut eliminator = Eliminator { not_terminals: not_terminal, span_type, bases: HashMap::new(), rewrites: Vec::new() };
+
+    for cycle in cycles {
+        let names: HashSet<String> = cycle.iter().map(|&i| eliminator.not_terminals[i].name.clone()).collect();
+        for (pos, &i) in cycle.iter().enumerate() {
+            for &j in &cycle[..pos] {
+                eliminator.substitute(i, j, &names);
+            }
+            eliminator.eliminate_direct(i);
+        }
+    }
+
+    let report = LeftRecursionReport { rewrites: eliminator.rewrites };
+    (ParserDescription { not_terminal: eliminator.not_terminals, ..parser_description }, report)
+}
+
+/// Name of the leftmost symbol of the rule, commands are skipped.
+fn first_call(rule: &Rule) -> Option<&str> {
+    match rule.members.iter().find(|member| !matches!(member, RuleMember::Command(_))) {
+        Some(RuleMember::RuleCall(RuleToken { name, .. })) => Some(name.as_str()),
+        _ => None,
+    }
+}
+
+fn starts_with_call(rule: &Rule, name: &str) -> bool {
+    matches!(rule.members.first(), Some(RuleMember::RuleCall(token)) if token.name == name)
+}
+
+/// Groups of mutually left recursive not terminals, ordered by declaration.
+fn get_left_recursive_cycles(not_terminals: &[NotTerminal]) -> Vec<Vec<usize>> {
+    let index: HashMap<&str, usize> = not_terminals
+        .iter()
+        .enumerate()
+        .map(|(i, not_term)| (not_term.name.as_str(), i))
+        .collect();
+
+    let edges: Vec<HashSet<usize>> = not_terminals
+        .iter()
+        .map(|not_term| {
+            not_term.rules
+                .iter()
+                .filter_map(|rule| first_call(rule).and_then(|name| index.get(name).copied()))
+                .collect()
+        })
+        .collect();
+
+    let reachable: Vec<HashSet<usize>> = (0..not_terminals.len())
+        .map(|start| {
+            let mut visited = HashSet::new();
+            let mut stack: Vec<usize> = edges[start].iter().copied().collect();
+            while let Some(next) = stack.pop() {
+                if visited.insert(next) {
+                    stack.extend(&edges[next]);
+                }
+            }
+            visited
+        })
+        .collect();
+
+    let mut assigned = HashSet::new();
+    let mut cycles = Vec::new();
+    for i in 0..not_terminals.len() {
+        if !reachable[i].contains(&i) || assigned.contains(&i) {
+            continue;
+        }
+        let cycle: Vec<usize> = (0..not_terminals.len())
+            .filter(|&j| reachable[i].contains(&j) && reachable[j].contains(&i))
+            .collect();
+        assigned.extend(cycle.iter().copied());
+        cycles.push(cycle);
+    }
+    cycles
+}
+
+impl Eliminator {
+    fn unsupported(&mut self, not_terminal: &str, reason: String) {
+        self.rewrites.push(LeftRecursionRewrite::Unsupported { not_terminal: not_terminal.to_string(), reason });
+    }
+
+    /// Replaces alternatives `A_i -> A_j γ` with the left recursive alternatives of `A_j`.
+    fn substitute(&mut self, i: usize, j: usize, cycle: &HashSet<String>) {
+        let NotTerminal { name: name_i, rules: rules_i, .. } = &self.not_terminals[i];
+        let NotTerminal { name: name_j, args: args_j, returns: returns_j, rules: rules_j, .. } = &self.not_terminals[j];
+        let (name_i, name_j) = (name_i.clone(), name_j.clone());
+
+        if !rules_i.iter().any(|rule| first_call(rule) == Some(name_j.as_str())) {
+            return;
+        }
+        if !args_j.is_empty() {
+            self.unsupported(&name_i, format!("`{name_j}` in its left recursive cycle has arguments"));
+            return;
+        }
+        if rules_i.iter().any(|rule| first_call(rule) == Some(name_j.as_str()) && !starts_with_call(rule, &name_j)) {
+            self.unsupported(&name_i, format!("a command precedes the left recursive call of `{name_j}`"));
+            return;
+        }
+
+        let (inlined, base): (Vec<Rule>, Vec<Rule>) = rules_j
+            .iter()
+            .cloned()
+            .partition(|rule| first_call(rule).is_some_and(|name| cycle.contains(name)));
+        if inlined.iter().any(|rule| !matches!(rule.members.first(), Some(RuleMember::RuleCall(_)))) {
+            self.unsupported(&name_i, format!("a command precedes the left recursive call in `{name_j}`"));
+            return;
+        }
+
+        let returns_j = returns_j.clone();
+        let rules_i = rules_i.clone();
+        let base = if base.is_empty() { None } else { Some(self.get_base(j, base)) };
+
+        let mut rules = Vec::new();
+        for rule in rules_i {
+            if !starts_with_call(&rule, &name_j) {
+                rules.push(rule);
+                continue;
+            }
+            for alternative in &inlined {
+                rules.push(inline_alternative(alternative, &rule, &name_j, &returns_j, &self.span_type));
+            }
+            if let Some(base) = &base {
+                rules.push(call_base(&rule, &name_j, base));
+            }
+        }
+
+        self.not_terminals[i].rules = rules;
+        self.rewrites.push(LeftRecursionRewrite::Substituted { not_terminal: name_i, inlined: name_j, base });
+    }
+
+    fn get_base(&mut self, j: usize, rules: Vec<Rule>) -> String {
+        let not_term = &self.not_terminals[j];
+        if let Some(base) = self.bases.get(&not_term.name) {
+            return base.clone();
+        }
+        let base = format!("{}_base", not_term.name);
+        let base_not_term = NotTerminal {
+            name: base.clone(),
+            args: not_term.args.clone(),
+            returns: not_term.returns.clone(),
+            rules,
+            position: not_term.position,
+        };
+        self.bases.insert(not_term.name.clone(), base.clone());
+        self.not_terminals.push(base_not_term);
+        base
+    }
+
+    fn eliminate_direct(&mut self, i: usize) {
+        let NotTerminal { name, args, returns, rules, position } = &self.not_terminals[i];
+        let (recursive, base): (Vec<&Rule>, Vec<&Rule>) = rules
+            .iter()
+            .partition(|rule| first_call(rule) == Some(name.as_str()));
+        if recursive.is_empty() {
+            return;
+        }
+
+        let own_args = format!("({})", args.iter().map(|a| a.name.as_str()).collect::<Vec<&str>>().join(","));
+        let reason = if base.is_empty() {
+            Some("every alternative is left recursive".to_string())
+        } else if recursive.iter().any(|rule| !starts_with_call(rule, name)) {
+            Some("a command precedes the left recursive call".to_string())
+        } else if recursive.iter().any(|rule| rule.members.len() == 1) {
+            Some(format!("alternative `{name}` derives only itself"))
+        } else if recursive.iter().any(|rule| starts_with_call(&Rule { members: rule.members[1..].to_vec() }, name)) {
+            Some(format!("alternative starts with two `{name}` calls"))
+        } else if recursive.iter().any(|rule| match &rule.members[0] {
+            RuleMember::RuleCall(RuleToken { args, .. }) => args.replace(char::is_whitespace, "") != own_args,
+            _ => true,
+        }) {
+            Some(format!("the left recursive call must pass the own arguments `{own_args}`"))
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            let name = name.clone();
+            self.unsupported(&name, reason);
+            return;
+        }
+
+        let tail = format!("{name}_tail");
+        let left_prefix = member_prefix(name, 0);
+        let mut left: Vec<Typed> = returns
+            .iter()
+            .map(|Typed { name, ty }| Typed { name: format!("{left_prefix}{name}"), ty: ty.clone() })
+            .collect();
+        let call_args = args
+            .iter()
+            .map(|a| format!("{}.clone()", a.name))
+            .chain(returns.iter().map(|r| r.name.clone()))
+            .collect::<Vec<String>>();
+
+        // The left operand of the tail covers everything the not terminal read so far.
+        let left_span = format!("{left_prefix}span");
+        let reads_span = recursive.iter().any(|rule| used_identifiers(&rule.members[1..]).contains(&left_span));
+        let call = |span: &str| -> RuleMember {
+            let mut call_args = call_args.clone();
+            if reads_span {
+                call_args.push(span.to_string());
+            }
+            let args = format!("({})", call_args.join(", "));
+            RuleMember::RuleCall(RuleToken { name: tail.clone(), args, annotation: Annotation::default(), position: 0 })
+        };
+        if reads_span {
+            left.push(Typed { name: left_span.clone(), ty: self.span_type.clone() });
+        }
+        let write_back = |index: usize| -> Option<RuleMember> {
+            if returns.is_empty() {
+                return None;
+            }
+            let prefix = member_prefix(&tail, index);
+            let code = returns
+                .iter()
+                .map(|Typed { name, .. }| format!("{name} = {prefix}{name};"))
+                .collect::<Vec<String>>()
+                .join(" ");
+            Some(RuleMember::Command(code))
+        };
+        let with_tail = |mut members: Vec<RuleMember>, span: &str| -> Rule {
+            let index = members.len();
+            members.push(call(span));
+            members.extend(write_back(index));
+            Rule { members }
+        };
+
+        let mut tail_rules: Vec<Rule> = recursive
+            .iter()
+            .map(|rule| {
+                let renames = moved_prefixes(&rule.members, 1, |k| k - 1);
+                let mut alpha = rule.members[1..].to_vec();
+                rewrite_member_identifiers(&mut alpha, &|ident| renamed_prefix(ident, &renames));
+                with_tail(alpha, &format!("{left_span}.to(self.span_since(span_start))"))
+            })
+            .collect();
+        let epsilon = returns
+            .iter()
+            .map(|Typed { name, .. }| format!("{name} = {left_prefix}{name};"))
+            .collect::<Vec<String>>()
+            .join(" ");
+        tail_rules.push(Rule { members: if epsilon.is_empty() { Vec::new() } else { vec![RuleMember::Command(epsilon)] } });
+
+        let base_rules: Vec<Rule> = base.into_iter().map(|rule| with_tail(rule.members.clone(), "self.span_since(span_start)")).collect();
+
+        let tail_not_term = NotTerminal {
+            name: tail.clone(),
+            args: args.iter().cloned().chain(left).collect(),
+            returns: returns.clone(),
+            rules: tail_rules,
+            position: *position,
+        };
+        let rewrite = LeftRecursionRewrite::Direct { not_terminal: name.clone(), tail, alternatives: recursive.len() };
+
+        self.not_terminals[i].rules = base_rules;
+        self.not_terminals.push(tail_not_term);
+        self.rewrites.push(rewrite);
+    }
+}
+
+/// `A_i -> A_j γ` with `A_j -> X δ` becomes `A_i -> X {let mut A_j0_res;} δ γ`,
+/// where `δ` assigns results of `A_j` to the `A_j0_*` variables read by `γ`.
+/// `A_j0_span` is the span of `X δ`, it's set after `δ` if `γ` reads it.
+fn inline_alternative(alternative: &Rule, rule: &Rule, name_j: &str, returns_j: &[Typed], span_type: &str) -> Rule {
+    let prefix = member_prefix(name_j, 0);
+    let declarations = returns_j
+        .iter()
+        .map(|Typed { name, ty }| format!("let mut {prefix}{name}: {ty};"))
+        .collect::<Vec<String>>()
+        .join(" ");
+    let shift = usize::from(!declarations.is_empty());
+
+    let delta_len = alternative.members.len() - 1;
+    let delta_renames = moved_prefixes(&alternative.members, 1, |k| k + shift);
+    let return_names: HashSet<&str> = returns_j.iter().map(|t| t.name.as_str()).collect();
+    let mut delta = alternative.members[1..].to_vec();
+    rewrite_member_identifiers(&mut delta, &|ident| {
+        if return_names.contains(ident) {
+            Some(format!("{prefix}{ident}"))
+        } else {
+            renamed_prefix(ident, &delta_renames)
+        }
+    });
+
+    let span = format!("{prefix}span");
+    let span_command = used_identifiers(&rule.members[1..])
+        .contains(&span)
+        .then(|| RuleMember::Command(format!("let {span}: {span_type} = self.span_since(span_start);")));
+
+    let gamma_shift = shift + delta_len + usize::from(span_command.is_some());
+    let gamma_renames = moved_prefixes(&rule.members, 1, |k| k + gamma_shift);
+    let mut gamma = rule.members[1..].to_vec();
+    rewrite_member_identifiers(&mut gamma, &|ident| renamed_prefix(ident, &gamma_renames));
+
+    let mut members = vec![alternative.members[0].clone()];
+    if shift == 1 {
+        members.push(RuleMember::Command(declarations));
+    }
+    members.extend(delta);
+    members.extend(span_command);
+    members.extend(gamma);
+    Rule { members }
+}
+
+fn call_base(rule: &Rule, name_j: &str, base: &str) -> Rule {
+    let mut members = rule.members.clone();
+    if let RuleMember::RuleCall(token) = &mut members[0] {
+        token.name = base.to_string();
+    }
+    let renames = vec![(member_prefix(name_j, 0), member_prefix(base, 0))];
+    rewrite_member_identifiers(&mut members[1..], &|ident| renamed_prefix(ident, &renames));
+    Rule { members }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_generator::parser_description::{parse_parser_description, NotTerminal, RuleMember, RuleToken};
+    use super::{eliminate_left_recursion, LeftRecursionReport, LeftRecursionRewrite};
+
+    fn eliminate(not_terminals: &str) -> (Vec<NotTerminal>, LeftRecursionReport) {
+        let grammar = format!(
+            "Prelude {{}} Skip {{}} Tokens {{ Num -> \"[0-9]+\"; Plus -> \"\\\\+\"; Comb -> \"\\\\$\"; }} NotTerminals {{ {not_terminals} }}"
+        );
+        let (parser_description, report) = eliminate_left_recursion(parse_parser_description(&grammar).unwrap());
+        (parser_description.not_terminal, report)
+    }
+
+    fn members(not_term: &NotTerminal, rule: usize) -> Vec<String> {
+        not_term.rules[rule].members
+            .iter()
+            .map(|member| match member {
+                RuleMember::RuleCall(RuleToken { name, args, .. }) => format!("{name}{args}"),
+                RuleMember::Command(code) => format!("{{{code}}}"),
+                RuleMember::Group(_) => "group".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn direct_recursion_moves_to_tail() {
+        let (not_terminals, report) = eliminate(
+            "E {} {res # i64} -> E Plus Num {res = E0_res + Num2_ident.parse::<i64>().unwrap();} | Num {res = Num0_ident.parse().unwrap();};"
+        );
+        assert_eq!(
+            vec![LeftRecursionRewrite::Direct { not_terminal: "E".to_string(), tail: "E_tail".to_string(), alternatives: 1 }],
+            report.rewrites,
+        );
+        assert_eq!(vec!["Num()", "{res = Num0_ident.parse().unwrap();}", "E_tail(res)", "{res = E_tail2_res;}"], members(&not_terminals[0], 0));
+
+        let tail = &not_terminals[1];
+        assert_eq!(vec!["E0_res"], tail.args.iter().map(|t| t.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(
+            vec!["Plus()", "Num()", "{res = E0_res + Num1_ident.parse::<i64>().unwrap();}", "E_tail(res)", "{res = E_tail3_res;}"],
+            members(tail, 0),
+        );
+        assert_eq!(vec!["{res = E0_res;}"], members(tail, 1));
+    }
+
+    #[test]
+    fn span_of_left_operand_is_passed_to_tail() {
+        let (not_terminals, _) = eliminate("E {} {} -> E Plus Num {println!(\"{:?}\", E0_span);} | Num;");
+        assert_eq!(vec!["Num()", "E_tail(self.span_since(span_start))"], members(&not_terminals[0], 0));
+
+        let tail = &not_terminals[1];
+        assert_eq!(vec!["E0_span"], tail.args.iter().map(|t| t.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(
+            vec!["Plus()", "Num()", "{println!(\"{:?}\", E0_span);}", "E_tail(E0_span.to(self.span_since(span_start)))"],
+            members(tail, 0),
+        );
+    }
+
+    #[test]
+    fn indirect_recursion_is_inlined() {
+        let (not_terminals, report) = eliminate(
+            "C {} {res # i64} -> D {res = D0_res;}; D {} {res # i64} -> C Comb Num {res = C0_res;} | Num {res = 0;};"
+        );
+        assert_eq!(2, report.rewrites.len());
+        assert_eq!(
+            LeftRecursionRewrite::Substituted { not_terminal: "D".to_string(), inlined: "C".to_string(), base: None },
+            report.rewrites[0],
+        );
+        assert_eq!(vec!["Num()", "{res = 0;}", "D_tail(res)", "{res = D_tail2_res;}"], members(&not_terminals[1], 0));
+        assert_eq!(
+            vec!["{let mut C0_res: i64;}", "{C0_res = D0_res;}", "Comb()", "Num()", "{res = C0_res;}", "D_tail(res)", "{res = D_tail5_res;}"],
+            members(&not_terminals[2], 0),
+        );
+    }
+
+    #[test]
+    fn recursion_without_base_is_reported() {
+        let (not_terminals, report) = eliminate("E {} {} -> E Plus;");
+        assert_eq!(1, not_terminals.len());
+        assert!(matches!(report.rewrites.as_slice(), [LeftRecursionRewrite::Unsupported { .. }]));
+    }
+}
@@ -0,0 +1,98 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+pub const EPSILON: &str = "ε";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ConflictKind {
+    FirstFirst,
+    FirstFollow,
+}
+
+impl Display for ConflictKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let string = match self {
+            ConflictKind::FirstFirst => "FIRST/FIRST",
+            ConflictKind::FirstFollow => "FIRST/FOLLOW",
+        };
+
+        f.write_str(string)
+    }
+}
+
+/// One alternative of a not terminal, `index` counts from zero.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Alternative {
+    pub index: usize,
+    pub text: String,
+}
+
+impl Display for Alternative {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{} `{}`", self.index + 1, self.text)
+    }
+}
+
+/// Rules explaining why `token` is in FOLLOW of a not terminal.
+///
+/// The first rule uses the conflicting not terminal, every next rule uses
+/// the left side of the previous one, the last rule puts `token` right after it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FollowChain {
+    pub token: String,
+    pub rules: Vec<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub not_terminal: String,
+    pub first: Alternative,
+    pub second: Alternative,
+    pub tokens: Vec<String>,
+    pub follow_chains: Vec<FollowChain>,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let tokens = self.tokens.join(", ");
+        match self.kind {
+            ConflictKind::FirstFirst => writeln!(
+                f,
+                "{} conflict in `{}`: alternatives {} and {} can both start with {tokens}",
+                self.kind, self.not_terminal, self.first, self.second,
+            )?,
+            ConflictKind::FirstFollow => writeln!(
+                f,
+                "{} conflict in `{}`: alternative {} derives {EPSILON} and FOLLOW({}) overlaps FIRST of alternative {} on {tokens}",
+                self.kind, self.not_terminal, self.first, self.not_terminal, self.second,
+            )?,
+        }
+
+        for FollowChain { token, rules } in &self.follow_chains {
+            writeln!(f, "    {token} is in FOLLOW({}):", self.not_terminal)?;
+            for rule in rules {
+                writeln!(f, "        {rule}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// All LL(1) conflicts found in a grammar.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConflictReport {
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Display for ConflictReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Not LL(1) grammar, {} conflict(s) found:", self.conflicts.len())?;
+        for conflict in &self.conflicts {
+            write!(f, "{conflict}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConflictReport {}
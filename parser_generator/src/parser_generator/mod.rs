@@ -1,19 +1,136 @@
 use crate::parser_generator::prelude_generator::generate_prelude;
-use crate::parser_generator::parser_description::parse_parser_description;
-use crate::parser_generator::parser_generator::generate_parser;
+use crate::parser_generator::syntax_tree::add_syntax_tree;
+use crate::parser_generator::ebnf::desugar_groups;
+use crate::parser_generator::labels::resolve_labels;
+use crate::parser_generator::left_factoring::factor_alternatives;
+use crate::parser_generator::left_recursion::eliminate_left_recursion;
+use crate::parser_generator::parser_description::{parse_parser_description, ParseTree, ParserDescription};
+use crate::parser_generator::parser_generator::{generate_parser, get_first_follow, get_prediction_table};
+use crate::parser_generator::table_generator::generate_table_parser;
 use crate::parser_generator::tokenizer_generator::generate_tokenizer;
+use crate::parser_generator::tree_builder::generate_tree_builders;
+use crate::parser_generator::validation::validate;
 
 mod tokenizer_generator;
+#[allow(clippy::module_inception)]
 mod parser_generator;
 mod prelude_generator;
 mod parser_description;
+mod ll1_conflict;
+mod generator_error;
+mod action_code;
+mod ebnf;
+mod labels;
+mod left_recursion;
+mod left_factoring;
+mod first_follow;
+mod names;
+mod table_generator;
+mod export;
+mod tree_builder;
+mod syntax_tree;
+mod validation;
 
+pub use crate::parser_generator::export::ExportFormat;
+pub use crate::parser_generator::first_follow::{FirstFollow, GrammarTables, NotTerminalSets, PredictionRow, PredictionTable};
+pub use crate::parser_generator::generator_error::GeneratorError;
+pub use crate::parser_generator::left_recursion::{LeftRecursionReport, LeftRecursionRewrite};
+pub use crate::parser_generator::ll1_conflict::{Alternative, Conflict, ConflictKind, ConflictReport, FollowChain};
+pub use crate::parser_generator::parser_description::GrammarError;
+pub use crate::parser_generator::validation::ValidationReport;
 
-pub fn generate_parser_from_string(grammar: String) -> String {
-    let (_, parser_description) = parse_parser_description(&grammar).unwrap();
+/// Optional grammar transformations and the layout of the generated code.
+#[derive(Debug, Clone, Default)]
+pub struct GeneratorOptions {
+    /// Extract common prefixes of alternatives into helper not terminals.
+    pub left_factoring: bool,
+    /// Wrap the generated code into `pub mod {module} { ... }`, so it can be `include!`d.
+    /// The `module` of the grammar's `Options` section takes precedence.
+    pub module: Option<String>,
+    /// Generate a parser driven by a parse table and an explicit stack instead of recursive
+    /// functions, so deeply nested input doesn't overflow the stack.
+    pub table_driven: bool,
+}
+
+/// Generated parser source and the rewrites applied to the grammar.
+pub struct GeneratedParser {
+    pub code: String,
+    /// Module the code is wrapped into, if any.
+    pub module: Option<String>,
+    pub left_recursion: LeftRecursionReport,
+}
+
+pub fn generate_parser_from_string(grammar: String, options: &GeneratorOptions) -> Result<GeneratedParser, GeneratorError> {
+    let (parser_description, left_recursion) = prepare_grammar(&grammar, options)?;
     let imports = generate_prelude(&parser_description);
     let tokenizer = generate_tokenizer(&parser_description);
-    let parser = generate_parser(&parser_description);
+    let tree_builders = generate_tree_builders(&parser_description);
+    let parser = match options.table_driven {
+        true => generate_table_parser(&parser_description)?,
+        false => generate_parser(&parser_description)?,
+    };
+
+    let mut code = format!("{imports}\n{tokenizer}\n{tree_builders}\n{parser}");
+    let grammar_options = &parser_description.options;
+    let module = grammar_options.module.clone().or_else(|| options.module.clone());
+    if let Some(module) = &module {
+        let visibility = grammar_options.visibility.as_deref().unwrap_or("pub");
+        code = format!("{visibility} mod {module} {{\n{code}\n}}\n");
+    }
+    Ok(GeneratedParser { code, module, left_recursion })
+}
+
+/// FIRST and FOLLOW sets of the grammar after all rewrites, conflicts are not checked.
+pub fn first_follow_from_string(grammar: &str, options: &GeneratorOptions) -> Result<FirstFollow, GeneratorError> {
+    let (parser_description, _) = prepare_grammar(grammar, options)?;
+    Ok(get_first_follow(&parser_description))
+}
+
+/// FIRST, FOLLOW and nullable sets and the prediction table of the grammar after all rewrites,
+/// conflicts are not checked but show up as cells predicting several rules.
+pub fn tables_from_string(grammar: &str, options: &GeneratorOptions) -> Result<GrammarTables, GeneratorError> {
+    let (parser_description, _) = prepare_grammar(grammar, options)?;
+    Ok(GrammarTables {
+        sets: get_first_follow(&parser_description),
+        prediction: get_prediction_table(&parser_description),
+    })
+}
+
+fn prepare_grammar(grammar: &str, options: &GeneratorOptions) -> Result<(ParserDescription, LeftRecursionReport), GeneratorError> {
+    let parser_description = parse_parser_description(grammar)?;
+    validate(&parser_description, grammar)?;
+    let mut parser_description = desugar_groups(resolve_labels(parser_description));
+    if matches!(parser_description.options.tree, ParseTree::Cst | ParseTree::Ast) {
+        parser_description = add_syntax_tree(parser_description, options.table_driven);
+    }
+    let (mut parser_description, left_recursion) = eliminate_left_recursion(parser_description);
+    if options.left_factoring {
+        parser_description = factor_alternatives(parser_description);
+    }
+    Ok((parser_description, left_recursion))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_parser_from_string, GeneratorOptions};
+
+    const CALCULATOR: &str = include_str!("../../resources/calculator.txt");
+
+    #[test]
+    fn options_section_names_module_and_items() {
+        let grammar = format!("Options {{ module = \"calc\"; visibility = \"pub(crate)\"; prefix = \"Calc\"; }}\n{CALCULATOR}");
+        let options = GeneratorOptions { module: Some("calculator".to_string()), ..GeneratorOptions::default() };
+        let parser = generate_parser_from_string(grammar, &options).unwrap();
+        assert_eq!(Some("calc".to_string()), parser.module);
+        assert!(parser.code.starts_with("pub(crate) mod calc {"));
+        assert!(parser.code.contains("pub(crate) enum CalcToken {"));
+        assert!(parser.code.contains("pub(crate) fn parse(input:String,) -> Result<(GraphVizNode, (i64)), CalcParseError>"));
+        assert!(parser.code.contains("CalcParser::new(CalcTokenizer::tokenize(input)?, builder)"));
+        assert!(parser.code.contains("pub(crate) trait CalcTreeBuilder {"));
 
-    format!("{imports}\n{tokenizer}\n{parser}")
+        let parser = generate_parser_from_string(CALCULATOR.to_string(), &options).unwrap();
+        assert_eq!(Some("calculator".to_string()), parser.module);
+        assert!(parser.code.starts_with("pub mod calculator {"));
+        assert!(parser.code.contains("pub enum Token {"));
+    }
 }
@@ -0,0 +1,100 @@
+use crate::parser_generator::parser_description::GrammarOptions;
+
+/// Names and visibility of the generated items.
+///
+/// Code templates refer to them as `$Token`, `$Tokenizer`, `$Parser`, `$ParseError`,
+/// `$Span`, `$Trivia`, `$TreeBuilder`, `$GraphVizBuilder`, `$NoTree`, `$Leaf`, `$ModeChange`, `$LexerMode`,
+/// `$Step`, `$Frame`, `$Returned` and `$vis`, see [`Names::fill`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Names {
+    pub token: String,
+    pub tokenizer: String,
+    pub parser: String,
+    pub parse_error: String,
+    pub span: String,
+    pub trivia: String,
+    pub tree_builder: String,
+    pub graph_viz_builder: String,
+    pub no_tree: String,
+    pub leaf: String,
+    pub mode_change: String,
+    pub lexer_mode: String,
+    pub step: String,
+    pub frame: String,
+    pub returned: String,
+    pub visibility: String,
+    prefix: String,
+}
+
+impl Names {
+    pub fn new(options: &GrammarOptions) -> Names {
+        let prefix = options.prefix.as_deref().unwrap_or("");
+        Names {
+            token: format!("{prefix}Token"),
+            tokenizer: format!("{prefix}Tokenizer"),
+            parser: format!("{prefix}Parser"),
+            parse_error: format!("{prefix}ParseError"),
+            span: format!("{prefix}Span"),
+            trivia: format!("{prefix}Trivia"),
+            tree_builder: format!("{prefix}TreeBuilder"),
+            graph_viz_builder: format!("{prefix}GraphVizBuilder"),
+            no_tree: format!("{prefix}NoTree"),
+            leaf: format!("{prefix}Leaf"),
+            mode_change: format!("{prefix}ModeChange"),
+            lexer_mode: format!("{prefix}LexerMode"),
+            step: format!("{prefix}Step"),
+            frame: format!("{prefix}Frame"),
+            returned: format!("{prefix}Returned"),
+            visibility: options.visibility.clone().unwrap_or("pub".to_string()),
+            prefix: prefix.to_string(),
+        }
+    }
+
+    /// Type of the concrete syntax tree node of a not terminal, e.g. `CalcENode` for `E`.
+    pub fn node(&self, not_terminal: &str) -> String {
+        format!("{}{not_terminal}Node", self.prefix)
+    }
+
+    /// Frame of a not terminal in the table-driven parser, e.g. `CalcEFrame` for `E`.
+    pub fn not_terminal_frame(&self, not_terminal: &str) -> String {
+        format!("{}{not_terminal}Frame", self.prefix)
+    }
+
+    pub fn fill(&self, template: &str) -> String {
+        template
+            .replace("$Tokenizer", &self.tokenizer)
+            .replace("$Token", &self.token)
+            .replace("$Parser", &self.parser)
+            .replace("$ParseError", &self.parse_error)
+            .replace("$Span", &self.span)
+            .replace("$Trivia", &self.trivia)
+            .replace("$TreeBuilder", &self.tree_builder)
+            .replace("$GraphVizBuilder", &self.graph_viz_builder)
+            .replace("$NoTree", &self.no_tree)
+            .replace("$Leaf", &self.leaf)
+            .replace("$ModeChange", &self.mode_change)
+            .replace("$LexerMode", &self.lexer_mode)
+            .replace("$Step", &self.step)
+            .replace("$Frame", &self.frame)
+            .replace("$Returned", &self.returned)
+            .replace("$vis", &self.visibility)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_generator::parser_description::GrammarOptions;
+    use super::Names;
+
+    #[test]
+    fn fills_prefixed_names() {
+        let options = GrammarOptions { prefix: Some("Calc".to_string()), visibility: Some("pub(crate)".to_string()), ..GrammarOptions::default() };
+        let names = Names::new(&options);
+        assert_eq!(
+            "pub(crate) fn tokenize(input: String) -> Result<Vec<CalcToken>, CalcParseError> { CalcTokenizer::new(input) } // CalcParser CalcSpan CalcTrivia",
+            names.fill("$vis fn tokenize(input: String) -> Result<Vec<$Token>, $ParseError> { $Tokenizer::new(input) } // $Parser $Span $Trivia"),
+        );
+        assert_eq!("static ref MODES: Vec<CalcLexerMode> // CalcModeChange::Pop", names.fill("static ref MODES: Vec<$LexerMode> // $ModeChange::Pop"));
+        assert_eq!("Token", Names::new(&GrammarOptions::default()).token);
+    }
+}
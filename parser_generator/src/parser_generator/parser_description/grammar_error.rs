@@ -0,0 +1,137 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use nom::error::{ContextError, ErrorKind, ParseError};
+
+/// Error accumulated by the nom parsers of a grammar file.
+///
+/// Keeps the furthest position the parser reached, what it expected there
+/// and the section of the grammar file it was parsing.
+#[derive(Debug)]
+pub struct DescriptionError<'a> {
+    input: &'a str,
+    kind: ErrorKind,
+    expected: Option<&'static str>,
+    section: Option<&'static str>,
+}
+
+impl<'a> DescriptionError<'a> {
+    pub fn expected(input: &'a str, expected: &'static str) -> DescriptionError<'a> {
+        DescriptionError { input, kind: ErrorKind::Verify, expected: Some(expected), section: None }
+    }
+
+    pub fn in_section(mut self, section: &'static str) -> DescriptionError<'a> {
+        self.section.get_or_insert(section);
+        self
+    }
+
+    pub fn into_grammar_error(self, source: &str) -> GrammarError {
+        let offset = source.len() - self.input.len();
+        let expected = match self.expected {
+            Some(expected) => expected.to_string(),
+            None => describe_kind(self.kind),
+        };
+        GrammarError::new(source, offset, self.section, expected)
+    }
+}
+
+impl<'a> ParseError<&'a str> for DescriptionError<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        DescriptionError { input, kind, expected: None, section: None }
+    }
+
+    fn append(_: &'a str, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: &'a str, c: char) -> Self {
+        DescriptionError { input, kind: ErrorKind::Char, expected: Some(describe_char(c)), section: None }
+    }
+
+    fn or(self, other: Self) -> Self {
+        if other.input.len() < self.input.len() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl<'a> ContextError<&'a str> for DescriptionError<'a> {
+    fn add_context(_: &'a str, context: &'static str, mut other: Self) -> Self {
+        other.expected.get_or_insert(context);
+        other
+    }
+}
+
+/// Malformed grammar file diagnostic with location and source snippet.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GrammarError {
+    pub line: usize,
+    pub column: usize,
+    pub section: Option<String>,
+    pub expected: String,
+    pub snippet: String,
+}
+
+impl GrammarError {
+    pub fn new(source: &str, offset: usize, section: Option<&str>, expected: String) -> GrammarError {
+        let (line, column) = line_and_column(source, offset);
+        let snippet = caret_snippet(source, line, column);
+        GrammarError { line, column, section: section.map(str::to_string), expected, snippet }
+    }
+}
+
+impl Display for GrammarError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected {}", self.expected)?;
+        if let Some(section) = &self.section {
+            write!(f, " in {section} section")?;
+        }
+        writeln!(f, " at line {}, column {}", self.line, self.column)?;
+        f.write_str(&self.snippet)
+    }
+}
+
+impl Error for GrammarError {}
+
+pub fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+pub fn caret_snippet(source: &str, line: usize, column: usize) -> String {
+    let text = source.lines().nth(line - 1).unwrap_or("").trim_end_matches('\r');
+    let number = line.to_string();
+    let gutter = " ".repeat(number.len());
+    let padding: String = text
+        .chars()
+        .take(column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    format!("{gutter} |\n{number} | {text}\n{gutter} | {padding}^\n")
+}
+
+fn describe_char(c: char) -> &'static str {
+    match c {
+        '{' => "`{`",
+        '}' => "`}`",
+        '(' => "`(`",
+        ')' => "`)`",
+        '#' => "`#` between argument name and type",
+        ';' => "`;`",
+        _ => "character",
+    }
+}
+
+fn describe_kind(kind: ErrorKind) -> String {
+    match kind {
+        ErrorKind::Eof => "end of grammar".to_string(),
+        ErrorKind::TakeWhile1 => "identifier".to_string(),
+        ErrorKind::TakeTill1 => "non-empty text".to_string(),
+        kind => format!("{kind:?}"),
+    }
+}
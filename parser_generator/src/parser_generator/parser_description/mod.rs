@@ -1,39 +1,131 @@
+use std::collections::{HashMap, HashSet};
 use std::ops::Add;
 
-use nom::IResult;
+use nom::{Finish, IResult};
 use nom::branch::alt;
-use nom::bytes::complete::{tag, take_till1, take_while1};
-use nom::character::complete::{char, multispace0};
+use nom::bytes::complete::{escaped, is_not, tag, take_till, take_till1, take_while1};
+use nom::character::complete::{anychar, char, i32 as integer, multispace0, one_of};
 
-use nom::combinator::{opt};
-use nom::Err::Error;
-use nom::error::{ErrorKind, ParseError};
+use nom::combinator::{all_consuming, cut, eof, map, not, opt, verify};
+use nom::Err::Failure;
+use nom::error::{context, ParseError};
 use nom::multi::{fold_many0, many0, separated_list0, separated_list1};
 use nom::sequence::{delimited, preceded, terminated, tuple};
 
 
-use self::RuleMember::{Command, RuleCall};
+use self::grammar_error::DescriptionError;
+use self::RuleMember::{Command, Group as GroupMember, RuleCall};
+
+pub use self::grammar_error::GrammarError;
+
+mod grammar_error;
 
 #[derive(Debug)]
 pub struct ParserDescription {
+    pub options: GrammarOptions,
     pub prelude: String,
     pub skip: Vec<String>,
+    /// Tokens of every mode in declaration order, a token may be declared in several modes.
     pub tokens: Vec<Token>,
+    /// Tokenizer modes of `Tokens mode Name { ... }` sections, without the default one.
+    pub modes: Vec<TokenMode>,
     pub not_terminal: Vec<NotTerminal>,
 }
 
+/// Settings of the optional `Options` section.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct GrammarOptions {
+    /// Module the generated code is wrapped into.
+    pub module: Option<String>,
+    /// Visibility of the module and the generated items, `pub` by default.
+    pub visibility: Option<String>,
+    /// Prefix of the generated type names, e.g. `Calc` for `CalcToken`.
+    pub prefix: Option<String>,
+    /// Keeps skipped text as trivia of the tokens instead of throwing it away.
+    pub trivia: Option<TriviaAttachment>,
+    /// Recovers from parse errors, `recovery = "Semi, Rb"` names the tokens parsing
+    /// resumes at besides the FOLLOW set of the failed not terminal.
+    pub recovery: Option<Vec<String>>,
+    /// Tree `parse` builds, `tree = "graph_viz"`, `tree = "cst"`, `tree = "ast"` or `tree = "none"`.
+    pub tree: ParseTree,
+}
+
+/// Tree built by `parse`, other trees are built by `parse_with` and a custom `TreeBuilder`.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum ParseTree {
+    /// `GraphVizNode`s of the `graph_viz` crate.
+    #[default]
+    GraphViz,
+    /// Concrete syntax tree of generated types, an enum per not terminal.
+    Cst,
+    /// Abstract syntax tree of generated types shaped by the annotations of the members.
+    Ast,
+    /// No tree, `parse` returns only the returns of the start not terminal.
+    None,
+}
+
+/// Token which skipped text is attached to, `trivia = "leading"` or `trivia = "trailing"`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TriviaAttachment {
+    /// Skipped text belongs to the next token, trivia after the last token to `Eof`.
+    Leading,
+    /// Skipped text belongs to the previous token, trivia before the first token to the first one.
+    Trailing,
+}
+
+/// Mode of the tokens of the `Tokens` section.
+pub const DEFAULT_MODE: &str = "Default";
+
 #[derive(Debug)]
 pub struct Token {
     pub name: String,
     pub regex: String,
+    /// Breaks ties between matches of the same length, `priority 1` after the regex.
+    /// Equal priorities are resolved by declaration order.
+    pub priority: i32,
+    /// Tokenizer mode the token is matched in.
+    pub mode: String,
+    /// Mode change after the token, `push(Mode)`, `pop` or `switch(Mode)` after the regex.
+    pub mode_change: Option<ModeChange>,
+    /// Value the token text is converted to, `: i64 = |s| s.parse()` at the end of the declaration.
+    pub payload: Option<TokenPayload>,
+    /// Offset of the name in the grammar text.
+    pub position: usize,
 }
 
+/// Type of a token value and the conversion from the token text, a function
+/// of `&str` returning `Result<Type, E>` where `E` implements `Display`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TokenPayload {
+    pub ty: String,
+    pub conversion: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ModeChange {
+    Push(String),
+    Pop,
+    Switch(String),
+}
+
+/// Named tokenizer mode, bare regexes of its section are skipped like the `Skip` section in the default mode.
+#[derive(Debug)]
+pub struct TokenMode {
+    pub name: String,
+    pub skip: Vec<String>,
+}
+
+/// Entry of a `Tokens` section with the positions of the mode it changes to and of its value type.
+type TokenEntry<'a> = (Token, Option<&'a str>, Option<&'a str>);
+
 #[derive(Debug)]
 pub struct NotTerminal {
     pub name: String,
     pub args: Vec<Typed>,
     pub returns: Vec<Typed>,
     pub rules: Vec<Rule>,
+    /// Offset of the name in the grammar text, helper not terminals have the one of their origin.
+    pub position: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -45,12 +137,44 @@ pub struct Rule {
 pub enum RuleMember {
     RuleCall(RuleToken),
     Command(String),
+    Group(Group),
+}
+
+/// Parenthesised alternatives with an optional `*`, `+` or `?` after them.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub alternatives: Vec<Rule>,
+    pub repetition: Repetition,
+    pub annotation: Annotation,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Repetition {
+    Once,
+    Optional,
+    ZeroOrMore,
+    OneOrMore,
 }
 
 #[derive(Debug, Clone)]
 pub struct RuleToken {
     pub name: String,
     pub args: String,
+    pub annotation: Annotation,
+    /// Offset of the name in the grammar text, `0` for calls added by rewrites.
+    pub position: usize,
+}
+
+/// How a member shows up in the node of its alternative with the `ast` tree.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Annotation {
+    /// `label=Member` or `label:Member`, name of the field of the member and
+    /// prefix of its results in actions, like `label.res`.
+    pub label: Option<String>,
+    /// `Member^`, the member's node is the node of the alternative.
+    pub promote: bool,
+    /// `Member!`, the member has no field.
+    pub drop: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -59,9 +183,11 @@ pub struct Typed {
     pub ty: String,
 }
 
-fn ws<'a, F: 'a, O, E: ParseError<&'a str>>(inner: F) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
+type PResult<'a, O> = IResult<&'a str, O, DescriptionError<'a>>;
+
+fn ws<'a, F, O, E: ParseError<&'a str>>(inner: F) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
     where
-        F: Fn(&'a str) -> IResult<&'a str, O, E>,
+        F: FnMut(&'a str) -> IResult<&'a str, O, E> + 'a,
 {
     delimited(
         multispace0,
@@ -70,134 +196,498 @@ fn ws<'a, F: 'a, O, E: ParseError<&'a str>>(inner: F) -> impl FnMut(&'a str) ->
     )
 }
 
-fn close_tag(str: &str) -> IResult<&str, (Option<&str>, &str)> {
-    delimited(
-        multispace0,
-        tuple((opt(tag(";")), ws(tag("}")))),
-        multispace0,
+fn section<'a, O, F>(name: &'static str, mut inner: F) -> impl FnMut(&'a str) -> PResult<'a, O>
+    where
+        F: FnMut(&'a str) -> PResult<'a, O>,
+{
+    move |s| inner(s).map_err(|e| e.map(|err| err.in_section(name)))
+}
+
+fn open_tag<'a>(name: &'static str, expected: &'static str) -> impl FnMut(&'a str) -> PResult<'a, char> {
+    preceded(context(expected, ws(tag(name))), cut(context(expected, char('{'))))
+}
+
+fn close_tag(str: &str) -> PResult<'_, (Option<&str>, &str)> {
+    context(
+        "`;` or `}`",
+        delimited(
+            multispace0,
+            tuple((opt(tag(";")), ws(tag("}")))),
+            multispace0,
+        ),
     )(str)
 }
 
-pub fn parse_parser_description(str: &str) -> IResult<&str, ParserDescription> {
-    let (s, (prelude, skip, tokens, not_terminal)) =
+pub fn parse_parser_description(str: &str) -> Result<ParserDescription, GrammarError> {
+    parse_sections(str)
+        .finish()
+        .map(|(_, mut parser_description)| {
+            locate(&mut parser_description, str);
+            resolve_group_args(&mut parser_description, str);
+            parser_description
+        })
+        .map_err(|err| err.into_grammar_error(str))
+}
+
+/// Parsers record positions as the length of the text left after them, as they don't see
+/// the whole grammar, this turns them into offsets in the grammar text.
+fn locate(parser_description: &mut ParserDescription, source: &str) {
+    for token in &mut parser_description.tokens {
+        token.position = source.len() - token.position;
+    }
+    for not_term in &mut parser_description.not_terminal {
+        not_term.position = source.len() - not_term.position;
+        for rule in &mut not_term.rules {
+            locate_members(rule, source.len());
+        }
+    }
+}
+
+/// Positions of the members of a rule parsed from text ending at offset `end`.
+fn locate_members(rule: &mut Rule, end: usize) {
+    for member in &mut rule.members {
+        match member {
+            RuleCall(token) => token.position = end - token.position,
+            GroupMember(group) => {
+                for alternative in &mut group.alternatives {
+                    locate_members(alternative, end);
+                }
+            }
+            Command(_) => {}
+        }
+    }
+}
+
+/// `E (A | B)` is parsed as a call of `E` with arguments `(A | B)`.
+/// If `E` takes no arguments, the brackets are re-read as a group.
+fn resolve_group_args(parser_description: &mut ParserDescription, source: &str) {
+    let with_args: HashSet<String> = parser_description
+        .not_terminal
+        .iter()
+        .filter(|not_term| !not_term.args.is_empty())
+        .map(|not_term| not_term.name.clone())
+        .collect();
+
+    for not_term in &mut parser_description.not_terminal {
+        for rule in &mut not_term.rules {
+            resolve_rule_group_args(rule, &with_args, source);
+        }
+    }
+}
+
+fn resolve_rule_group_args(rule: &mut Rule, with_args: &HashSet<String>, source: &str) {
+    let mut members = Vec::with_capacity(rule.members.len());
+    for member in rule.members.drain(..) {
+        match member {
+            RuleCall(RuleToken { name, args, annotation, position }) if args != "()" && !with_args.contains(&name) => {
+                let group = all_consuming(terminated(parse_group, multispace0))(&args)
+                    .map(|(_, group)| group)
+                    .ok();
+                match group {
+                    // The label was written before the call, `^` or `!` after the group.
+                    Some(GroupMember(mut group)) => {
+                        let args_start = position + source[position..].find('(').unwrap_or(0);
+                        for alternative in &mut group.alternatives {
+                            locate_members(alternative, args_start + args.len());
+                        }
+                        let call_annotation = Annotation { label: annotation.label, ..Annotation::default() };
+                        let group_annotation = Annotation { label: None, ..annotation };
+                        members.push(RuleCall(RuleToken { name, args: "()".to_string(), annotation: call_annotation, position }));
+                        members.push(GroupMember(Group { annotation: group_annotation, ..group }));
+                    }
+                    _ => members.push(RuleCall(RuleToken { name, args, annotation, position })),
+                }
+            }
+            GroupMember(mut group) => {
+                for alternative in &mut group.alternatives {
+                    resolve_rule_group_args(alternative, with_args, source);
+                }
+                members.push(GroupMember(group));
+            }
+            member => members.push(member),
+        }
+    }
+    rule.members = members;
+}
+
+fn parse_sections(str: &str) -> PResult<'_, ParserDescription> {
+    let (s, (options, prelude, skip, tokens, modes, not_terminal)) =
         tuple(
             (
-                delimited(
-                    tuple((ws(tag("Prelude")), tag("{"))),
-                    |s| parse_balanced(s, '{', '}'),
-                    tag("}"),
-                )
-                ,
-                delimited(
-                    tuple((ws(tag("Skip")), tag("{"))),
-                    parse_regexes,
-                    close_tag,
+                opt(section(
+                    "Options",
+                    delimited(
+                        open_tag("Options", "`Options {`"),
+                        cut(parse_options),
+                        cut(close_tag),
+                    ),
+                )),
+                section(
+                    "Prelude",
+                    delimited(
+                        open_tag("Prelude", "`Prelude {`"),
+                        cut(|s| parse_balanced(s, '{', '}')),
+                        cut(char('}')),
+                    ),
+                ),
+                section(
+                    "Skip",
+                    delimited(
+                        open_tag("Skip", "`Skip {`"),
+                        cut(parse_regexes),
+                        cut(close_tag),
+                    ),
                 ),
-                delimited(
-                    tuple((ws(tag("Tokens")), tag("{"))),
-                    parse_tokens,
-                    close_tag,
+                section(
+                    "Tokens",
+                    delimited(
+                        open_tag("Tokens", "`Tokens {`"),
+                        cut(parse_tokens),
+                        cut(close_tag),
+                    ),
                 ),
-                delimited(
-                    tuple((ws(tag("NotTerminals")), tag("{"))),
-                    parse_not_terminals,
-                    close_tag,
+                many0(section("Tokens", parse_mode_section)),
+                section(
+                    "NotTerminals",
+                    delimited(
+                        open_tag("NotTerminals", "`NotTerminals {`"),
+                        cut(parse_not_terminals),
+                        cut(close_tag),
+                    ),
                 ),
             ),
         )(str)?;
-    Ok((s, ParserDescription { prelude, skip, tokens, not_terminal }))
+    let (s, _) = context("end of grammar", eof)(s)?;
+    let (options, recovery_position) = options.unwrap_or_default();
+
+    let mut entries = tokens;
+    let mut token_modes = Vec::new();
+    for (mode, mode_entries) in modes {
+        entries.extend(mode_entries);
+        token_modes.push(mode);
+    }
+    check_modes(&entries, &token_modes).map_err(|err| Failure(err.in_section("Tokens")))?;
+    let tokens = share_payloads(entries).map_err(|err| Failure(err.in_section("Tokens")))?;
+    let unknown_sync = options
+        .recovery
+        .iter()
+        .flatten()
+        .any(|sync| !tokens.iter().any(|token: &Token| &token.name == sync));
+    if let Some(position) = recovery_position.filter(|_| unknown_sync) {
+        return Err(Failure(DescriptionError::expected(position, "names of declared tokens").in_section("Options")));
+    }
+    let modes = token_modes.into_iter().map(|(mode, _)| mode).collect();
+    Ok((s, ParserDescription { options, prelude, skip, tokens, modes, not_terminal }))
+}
+
+/// Mode names must be unique and every mode change must name a declared mode.
+fn check_modes<'a>(entries: &[TokenEntry<'a>], modes: &[(TokenMode, &'a str)]) -> Result<(), DescriptionError<'a>> {
+    let mut declared = HashSet::from([DEFAULT_MODE]);
+    for (mode, position) in modes {
+        if !declared.insert(&mode.name) {
+            return Err(DescriptionError::expected(position, "new mode name"));
+        }
+    }
+    for (token, position, _) in entries {
+        let target = match &token.mode_change {
+            Some(ModeChange::Push(target) | ModeChange::Switch(target)) => target,
+            _ => continue,
+        };
+        if let Some(position) = position.filter(|_| !declared.contains(target.as_str())) {
+            return Err(DescriptionError::expected(position, "name of a declared mode"));
+        }
+    }
+    Ok(())
+}
+
+/// A token declared in several modes has the value type of its first declaration,
+/// later declarations may leave it out but can't declare another one.
+fn share_payloads(entries: Vec<TokenEntry<'_>>) -> Result<Vec<Token>, DescriptionError<'_>> {
+    let mut payloads: HashMap<String, Option<TokenPayload>> = HashMap::new();
+    let mut tokens = Vec::new();
+    for (mut token, _, position) in entries {
+        match payloads.get(&token.name) {
+            None => {
+                payloads.insert(token.name.clone(), token.payload.clone());
+            }
+            Some(first) if token.payload.is_none() => token.payload = first.clone(),
+            Some(first) if *first != token.payload => {
+                let expected = match first {
+                    Some(_) => "value type and conversion of the first declaration",
+                    None => "no value type like the first declaration",
+                };
+                return Err(DescriptionError::expected(position.expect("declared value types have a position"), expected));
+            }
+            Some(_) => {}
+        }
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// `Tokens mode Name { ... }`, its entries are tokens and bare skip regexes.
+fn parse_mode_section(str: &str) -> PResult<'_, ((TokenMode, &str), Vec<TokenEntry<'_>>)> {
+    let (s, _) = preceded(ws(tag("Tokens")), cut(context("`mode` after `Tokens`", tag("mode"))))(str)?;
+    let (position, _) = multispace0(s)?;
+    let (s, name) = cut(context("mode name", take_while1(|c: char| c.is_alphabetic())))(position)?;
+    let (s, _) = cut(context("`{`", ws(char('{'))))(s)?;
+    let (s, entries) = cut(separated_list0(
+        tag(";"),
+        alt((map(parse_token, Ok), map(ws(parse_regex), Err))),
+    ))(s)?;
+    let (s, _) = cut(close_tag)(s)?;
+
+    let mut tokens = Vec::new();
+    let mut skip = Vec::new();
+    for entry in entries {
+        match entry {
+            Ok((token, target, payload)) => tokens.push((Token { mode: name.to_string(), ..token }, target, payload)),
+            Err(regex) => skip.push(regex),
+        }
+    }
+    Ok((s, ((TokenMode { name: name.to_string(), skip }, position), tokens)))
 }
 
+/// Options with the position of the `recovery` tokens, they are checked once the tokens are parsed.
+fn parse_options(str: &str) -> PResult<'_, (GrammarOptions, Option<&str>)> {
+    let (s, entries) = separated_list0(tag(";"), parse_option)(str)?;
+    let mut options = GrammarOptions::default();
+    let mut recovery_position = None;
+    for (name, name_position, content, position) in entries {
+        match (name, content) {
+            ("module", module) if is_identifier(module) => options.module = Some(module.to_string()),
+            ("prefix", prefix) if is_identifier(prefix) => options.prefix = Some(prefix.to_string()),
+            ("visibility", visibility @ ("pub" | "pub(crate)" | "pub(super)")) => options.visibility = Some(visibility.to_string()),
+            ("trivia", "leading") => options.trivia = Some(TriviaAttachment::Leading),
+            ("trivia", "trailing") => options.trivia = Some(TriviaAttachment::Trailing),
+            ("tree", "graph_viz") => options.tree = ParseTree::GraphViz,
+            ("tree", "cst") => options.tree = ParseTree::Cst,
+            ("tree", "ast") => options.tree = ParseTree::Ast,
+            ("tree", "none") => options.tree = ParseTree::None,
+            ("recovery", tokens) if tokens.chars().all(|c| c.is_alphabetic() || c == ',' || c == ' ') => {
+                let sync = tokens.split(',').map(str::trim).filter(|token| !token.is_empty());
+                options.recovery = Some(sync.map(str::to_string).collect());
+                recovery_position = Some(position);
+            }
+            _ => {
+                let (input, expected) = match name {
+                    "module" => (position, "module name"),
+                    "prefix" => (position, "type name prefix"),
+                    "visibility" => (position, "`pub`, `pub(crate)` or `pub(super)` visibility"),
+                    "trivia" => (position, "`leading` or `trailing` trivia"),
+                    "recovery" => (position, "comma separated token names"),
+                    "tree" => (position, "`graph_viz`, `cst`, `ast` or `none` tree"),
+                    _ => (name_position, "`module`, `visibility`, `prefix`, `trivia`, `recovery` or `tree` option"),
+                };
+                return Err(Failure(DescriptionError::expected(input, expected)));
+            }
+        }
+    }
+    Ok((s, (options, recovery_position)))
+}
 
-fn parse_tokens(str: &str) -> IResult<&str, Vec<Token>> {
+/// Parses `name = "value"` into the name and the value, each with the input starting at it.
+fn parse_option(str: &str) -> PResult<'_, (&str, &str, &str, &str)> {
+    let (name_position, _) = multispace0(str)?;
+    let (s, name) = ws(take_while1(|c: char| c.is_alphabetic()))(name_position)?;
+    let (value, _) = cut(context("`=` after option name", tag("=")))(s)?;
+    let (s, content) = cut(ws(context("option value in quotes", parse_quoted)))(value)?;
+    Ok((s, (name, name_position, content, value.trim_start())))
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with(|c: char| c.is_ascii_digit()) && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn parse_quoted(str: &str) -> PResult<'_, &str> {
+    delimited(
+        tag("\""),
+        cut(take_till(|c| c == '\"')),
+        cut(context("closing `\"`", tag("\""))),
+    )(str)
+}
+
+
+fn parse_tokens(str: &str) -> PResult<'_, Vec<TokenEntry<'_>>> {
     separated_list0(tag(";"), parse_token)(str)
 }
 
-fn parse_regexes(str: &str) -> IResult<&str, Vec<String>> {
+fn parse_regexes(str: &str) -> PResult<'_, Vec<String>> {
     separated_list0(tag(";"), ws(parse_regex))(str)
 }
 
-fn parse_token(str: &str) -> IResult<&str, Token> {
-    let (s, (name, regex)) = tuple((
-        terminated(ws(take_while1(|c: char| c.is_alphabetic())), tag("->")),
-        ws(parse_regex)
+fn parse_token(str: &str) -> PResult<'_, TokenEntry<'_>> {
+    let (position, _) = multispace0(str)?;
+    let (s, (name, regex, priority, mode_change)) = tuple((
+        ws(take_while1(|c: char| c.is_alphabetic())),
+        cut(preceded(
+            context("`->` after token name", tag("->")),
+            ws(context("token regex in quotes", parse_regex)),
+        )),
+        opt(preceded(
+            tag("priority"),
+            cut(ws(context("integer priority", integer))),
+        )),
+        opt(ws(parse_mode_change)),
     )
-    )(str)?;
+    )(position)?;
+    let (s, payload) = opt(preceded(char(':'), cut(parse_payload)))(s)?;
+
+    let (mode_change, target) = match mode_change {
+        Some((mode_change, target)) => (Some(mode_change), target),
+        None => (None, None),
+    };
+    let token = Token {
+        name: name.to_string(),
+        regex,
+        priority: priority.unwrap_or(0),
+        mode: DEFAULT_MODE.to_string(),
+        mode_change,
+        payload: payload.as_ref().map(|(payload, _)| payload.clone()),
+        position: position.len(),
+    };
+    Ok((s, (token, target, payload.map(|(_, position)| position))))
+}
 
-    Ok((s, Token { name: name.to_string(), regex }))
+/// `Type = conversion` of a token with the position of the type, the conversion ends at `;` or `}` outside of brackets.
+fn parse_payload(str: &str) -> PResult<'_, (TokenPayload, &str)> {
+    let (position, _) = multispace0(str)?;
+    let (s, ty) = context("value type", take_while1(|c: char| c != '=' && c != ';' && c != '}'))(position)?;
+    let (s, _) = context("`=` after value type", tag("="))(s)?;
+    let (s, conversion) = context("conversion expression", verify(take_expression, |e: &str| !e.trim().is_empty()))(s)?;
+    let payload = TokenPayload { ty: ty.trim().to_string(), conversion: conversion.trim().to_string() };
+    Ok((s, (payload, position)))
 }
 
+fn take_expression(str: &str) -> PResult<'_, &str> {
+    let mut depth: usize = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut skip_to = 0;
+    for (i, c) in str.char_indices() {
+        match c {
+            _ if i < skip_to => {}
+            _ if in_string => {
+                in_string = c != '"' || escaped;
+                escaped = c == '\\' && !escaped;
+            }
+            '"' => in_string = true,
+            '\'' => skip_to = char_literal_end(&str[i..]).map_or(0, |end| i + end),
+            '(' | '[' | '{' => depth += 1,
+            ';' | '}' if depth == 0 => return Ok((&str[i..], &str[..i])),
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    Ok(("", str))
+}
 
-fn parse_regex(str: &str) -> IResult<&str, String> {
-    let (s1, s2) = delimited(
-        tag("\""),
-        take_till1(|c| c == '\"'),
+/// Length of the char literal like `'x'` or `'\''` at the start of the text, `None` for a lifetime.
+fn char_literal_end(str: &str) -> Option<usize> {
+    let mut chars = str.char_indices().skip(1);
+    match chars.next()? {
+        (_, '\\') => chars.skip(1).find(|(_, c)| *c == '\'').map(|(i, _)| i + 1),
+        _ => chars.next().filter(|(_, c)| *c == '\'').map(|(i, _)| i + 1),
+    }
+}
+
+/// `pop`, `push(Mode)` or `switch(Mode)` with the position of the mode name.
+fn parse_mode_change(str: &str) -> PResult<'_, (ModeChange, Option<&str>)> {
+    if let Ok((s, _)) = tag::<_, _, DescriptionError>("pop")(str) {
+        return Ok((s, (ModeChange::Pop, None)));
+    }
+    let (s, keyword) = alt((tag("push"), tag("switch")))(str)?;
+    let (target, _) = cut(context("`(` after mode change", ws(char('('))))(s)?;
+    let (s, name) = cut(context("mode name", take_while1(|c: char| c.is_alphabetic())))(target)?;
+    let (s, _) = cut(context("`)` after mode name", ws(char(')'))))(s)?;
+
+    let mode_change = match keyword {
+        "push" => ModeChange::Push(name.to_string()),
+        _ => ModeChange::Switch(name.to_string()),
+    };
+    Ok((s, (mode_change, Some(target))))
+}
+
+
+/// Regex as a Rust string literal, `\"` doesn't close it.
+fn parse_regex(str: &str) -> PResult<'_, String> {
+    let (s1, s2) = preceded(
         tag("\""),
+        cut(terminated(
+            context("non-empty regex", verify(escaped(is_not("\\\""), '\\', anychar), |s: &str| !s.is_empty())),
+            context("closing `\"` of regex", tag("\"")),
+        )),
     )(str)?;
 
     Ok((s1, format!("\"{s2}\"")))
 }
 
-fn parse_typed(str: &str) -> IResult<&str, Typed> {
-    let (s, typed) = tuple(
+fn parse_typed(str: &str) -> PResult<'_, Typed> {
+    let (s, (name, ty)) = tuple(
         (
-            delimited(multispace0, take_till1(|c: char| c == '#' || c == '}'), char('#')),
-            ws(take_till1(|c| c == '}' || c == ';'))
+            preceded(multispace0, take_till1(|c: char| c == '#' || c == '}')),
+            cut(preceded(
+                char('#'),
+                ws(context("argument type", take_till1(|c| c == '}' || c == ';'))),
+            )),
         )
-    )(str)
-        .map(
-            |(a, (s1, s2))|
-                (a, Typed { name: s1.trim().to_string(), ty: s2.trim().to_string() })
-        )?;
+    )(str)?;
+    let typed = Typed { name: name.trim().to_string(), ty: ty.trim().to_string() };
     if typed.name.is_empty() || typed.ty.is_empty() {
-        let err: nom::error::Error<&str> = nom::error::Error { input: str, code: ErrorKind::Verify };
-        return Err(Error(err));
+        return Err(Failure(DescriptionError::expected(str, "`name # Type` argument")));
     }
     Ok((s, typed))
 }
 
-fn parse_args(str: &str) -> IResult<&str, Vec<Typed>> {
-    delimited(
-        char('{'),
-        separated_list0(char(';'), parse_typed),
-        char('}'),
+fn parse_args(str: &str) -> PResult<'_, Vec<Typed>> {
+    preceded(
+        context("`{` with argument list", char('{')),
+        cut(terminated(
+            separated_list0(char(';'), parse_typed),
+            preceded(multispace0, char('}')),
+        )),
     )(str)
 }
 
-fn parse_args_in_bracket(str: &str) -> IResult<&str, String> {
-    let (s, (l, args, r)) = tuple((
+fn parse_args_in_bracket(str: &str) -> PResult<'_, String> {
+    let (s, (l, (args, r))) = tuple((
         char('('),
-        |s| parse_balanced(s, '(', ')'),
-        char(')')
+        cut(tuple((
+            |s| parse_balanced(s, '(', ')'),
+            char(')'),
+        ))),
     )
     )(str)?;
 
     Ok((s, format!("{l}{args}{r}")))
 }
 
-fn parse_not_terminals(str: &str) -> IResult<&str, Vec<NotTerminal>> {
+fn parse_not_terminals(str: &str) -> PResult<'_, Vec<NotTerminal>> {
     separated_list0(tag(";"), parse_not_terminal)(str)
 }
 
-fn parse_not_terminal(str: &str) -> IResult<&str, NotTerminal> {
-    let (a, (name, args, returns, rules)) =
+fn parse_not_terminal(str: &str) -> PResult<'_, NotTerminal> {
+    let (position, _) = multispace0(str)?;
+    let (a, (name, (args, returns, rules))) =
         tuple((
                   ws(take_while1(|c: char| c.is_alphabetic())),
-                  ws(parse_args),
-                  ws(parse_args),
-                  delimited(
-                      tag("->"),
-                      separated_list1(tag("|"), parse_rule),
-                      multispace0,
-                  ),
+                  cut(tuple((
+                      ws(parse_args),
+                      ws(parse_args),
+                      delimited(
+                          context("`->` before rules", tag("->")),
+                          separated_list1(tag("|"), parse_rule),
+                          multispace0,
+                      ),
+                  ))),
               ),
-        )(str)?;
-    let not_terminal = NotTerminal { name: name.to_string(), args, returns, rules };
+        )(position)?;
+    let not_terminal = NotTerminal { name: name.to_string(), args, returns, rules, position: position.len() };
     Ok((a, not_terminal))
 }
 
 
-fn parse_rule(str: &str) -> IResult<&str, Rule> {
+fn parse_rule(str: &str) -> PResult<'_, Rule> {
     let (s1, members) = delimited(
         multispace0,
         many0(parse_rule_member),
@@ -206,58 +696,324 @@ fn parse_rule(str: &str) -> IResult<&str, Rule> {
     Ok((s1, Rule { members }))
 }
 
-fn parse_rule_member(str: &str) -> IResult<&str, RuleMember> {
+fn parse_rule_member(str: &str) -> PResult<'_, RuleMember> {
     delimited(
         multispace0,
-        alt((parse_rule_token, parse_command)),
+        alt((parse_annotated, parse_command)),
         multispace0,
     )(str)
 }
 
-fn parse_rule_token(str: &str) -> IResult<&str, RuleMember> {
+/// Call or group with its annotation, `label=Member` or `label:Member`, `Member^` or `Member!`.
+fn parse_annotated(str: &str) -> PResult<'_, RuleMember> {
+    let (s, label) = opt(terminated(
+        take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+        preceded(multispace0, one_of("=:")),
+    ))(str)?;
+    let (s, member) = match label {
+        Some(_) => cut(context("rule member after label", ws(alt((parse_rule_token, parse_group)))))(s)?,
+        None => alt((parse_rule_token, parse_group))(s)?,
+    };
+    let (s, suffix) = opt(preceded(multispace0, one_of("^!")))(s)?;
+
+    let annotation = Annotation {
+        label: label.map(str::to_string),
+        promote: suffix == Some('^'),
+        drop: suffix == Some('!'),
+    };
+    let member = match member {
+        RuleCall(token) => RuleCall(RuleToken { annotation, ..token }),
+        GroupMember(group) => GroupMember(Group { annotation, ..group }),
+        command => command,
+    };
+    Ok((s, member))
+}
+
+fn parse_rule_token(str: &str) -> PResult<'_, RuleMember> {
     let (a, (s1, s2)) =
         tuple(
             (take_while1(|c: char| c.is_alphabetic()),
-             preceded(multispace0, opt(parse_args_in_bracket))
+             preceded(multispace0, opt(terminated(parse_args_in_bracket, not(one_of("*+?")))))
             )
         )(str)?;
-    Ok((a, RuleCall(RuleToken { name: s1.to_string(), args: s2.unwrap_or("()".to_string()) })))
+    let args = s2.unwrap_or("()".to_string());
+    let position = str.len();
+    Ok((a, RuleCall(RuleToken { name: s1.to_string(), args, annotation: Annotation::default(), position })))
+}
+
+fn parse_group(str: &str) -> PResult<'_, RuleMember> {
+    let (s, (alternatives, repetition)) = tuple((
+        preceded(
+            char('('),
+            cut(terminated(
+                separated_list1(tag("|"), parse_rule),
+                context("`)` closing the group", char(')')),
+            )),
+        ),
+        opt(one_of("*+?")),
+    ))(str)?;
+
+    let repetition = match repetition {
+        Some('*') => Repetition::ZeroOrMore,
+        Some('+') => Repetition::OneOrMore,
+        Some('?') => Repetition::Optional,
+        _ => Repetition::Once,
+    };
+    Ok((s, GroupMember(Group { alternatives, repetition, annotation: Annotation::default() })))
 }
 
 
-fn parse_command(str: &str) -> IResult<&str, RuleMember> {
-    let (a, b) = delimited(
-        tag("{"),
-        |s| parse_balanced(s, '{', '}'),
-        tag("}"),
+fn parse_command(str: &str) -> PResult<'_, RuleMember> {
+    let (a, b) = preceded(
+        char('{'),
+        cut(terminated(
+            |s| parse_balanced(s, '{', '}'),
+            char('}'),
+        )),
     )(str)?;
 
     Ok((a, Command(b)))
 }
 
-fn parse_inner_balanced<'a>(str: &'a str, left_bracket: char, right_bracket: char) -> IResult<&'a str, String> {
-    let (s, (s1, s2, s3)) = tuple(
+fn parse_inner_balanced(str: &str, left_bracket: char, right_bracket: char) -> PResult<'_, String> {
+    let (s, (s1, (s2, s3))) = tuple(
         (char(left_bracket),
-         (|s: &'a str| parse_balanced(s, left_bracket, right_bracket)),
-         char(right_bracket))
+         cut(tuple((
+             |s| parse_balanced(s, left_bracket, right_bracket),
+             char(right_bracket),
+         ))))
     )
         (str)?;
 
     Ok((s, format!("{s1}{s2}{s3}")))
 }
 
-fn parse_balanced<'a>(str: &'a str, left_bracket: char, right_bracket: char) -> IResult<&'a str, String> {
+fn parse_balanced<'a>(str: &'a str, left_bracket: char, right_bracket: char) -> PResult<'a, String> {
     let (s, str) = fold_many0(
         alt((
-                |x: &'a str|
-                    (take_till1(|c: char| c == left_bracket || c == right_bracket)(x)
-                        .map(|(a, b)| (a, b.to_string()))),
+                |x: &'a str| {
+                    take_till1(|c: char| c == left_bracket || c == right_bracket)(x)
+                        .map(|(a, b)| (a, b.to_string()))
+                },
                 |s| parse_inner_balanced(s, left_bracket, right_bracket)
             ),
         ),
-        || String::new(),
+        String::new,
         |acc: String, item: String| acc.add(&item),
     )(str)?;
     Ok((s, str))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::{parse_parser_description, Annotation, ModeChange, ParseTree, RuleMember, TokenPayload, TriviaAttachment};
+
+    const CALCULATOR: &str = include_str!("../../../resources/calculator.txt");
+
+    #[test]
+    fn parses_calculator() {
+        let description = parse_parser_description(CALCULATOR).unwrap();
+        assert_eq!(8, description.tokens.len());
+        assert_eq!(9, description.not_terminal.len());
+    }
+
+    #[test]
+    fn missing_semicolon_between_tokens() {
+        let grammar = CALCULATOR.replace("Mul -> \"\\\\*\";", "Mul -> \"\\\\*\"");
+        let err = parse_parser_description(&grammar).unwrap_err();
+        assert_eq!(Some("Tokens".to_string()), err.section);
+        assert_eq!("`;` or `}`", err.expected);
+        assert_eq!((31, 5), (err.line, err.column));
+        assert!(err.snippet.contains("31 |     Plus -> \"\\\\+\";\n   |     ^"));
+    }
+
+    #[test]
+    fn missing_arrow_in_token() {
+        let grammar = CALCULATOR.replace("Div -> ", "Div ");
+        let err = parse_parser_description(&grammar).unwrap_err();
+        assert_eq!(Some("Tokens".to_string()), err.section);
+        assert_eq!("`->` after token name", err.expected);
+        assert_eq!((33, 9), (err.line, err.column));
+    }
+
+    #[test]
+    fn token_priority() {
+        let grammar = CALCULATOR.replace("Div -> \"/\";", "Div -> \"/\" priority -2;");
+        let tokens = parse_parser_description(&grammar).unwrap().tokens;
+        assert_eq!(vec![0, 0, 0, 0, -2, 0, 0, 0], tokens.iter().map(|t| t.priority).collect::<Vec<_>>());
+
+        let grammar = CALCULATOR.replace("Div -> \"/\";", "Div -> \"/\" priority high;");
+        let err = parse_parser_description(&grammar).unwrap_err();
+        assert_eq!("integer priority", err.expected);
+        assert_eq!((33, 25), (err.line, err.column));
+    }
+
+    #[test]
+    fn token_modes() {
+        let modes = "Tokens mode Str { Text -> \"[^\\\\$\\\"]+\"; Quote -> \"\\\"\" pop; Lb -> \"\\\\$\\\\(\" push(Default); }\n\
+                     Tokens mode Raw { \"\\\\s+\"; Rb -> \"\\\\)\" switch(Str); }\n";
+        let grammar = CALCULATOR
+            .replace("Rb -> \"\\\\)\";", "Rb -> \"\\\\)\"; Quote -> \"\\\"\" push(Str);")
+            .replace("NotTerminals {", &format!("{modes}NotTerminals {{"));
+        let description = parse_parser_description(&grammar).unwrap();
+
+        let names: Vec<_> = description.modes.iter().map(|mode| (mode.name.as_str(), mode.skip.clone())).collect();
+        assert_eq!(vec![("Str", vec![]), ("Raw", vec!["\"\\\\s+\"".to_string()])], names);
+        let changes: Vec<_> = description.tokens[8..].iter().map(|t| (t.name.as_str(), t.mode.as_str(), t.mode_change.clone())).collect();
+        assert_eq!(
+            vec![
+                ("Quote", "Default", Some(ModeChange::Push("Str".to_string()))),
+                ("Text", "Str", None),
+                ("Quote", "Str", Some(ModeChange::Pop)),
+                ("Lb", "Str", Some(ModeChange::Push("Default".to_string()))),
+                ("Rb", "Raw", Some(ModeChange::Switch("Str".to_string()))),
+            ],
+            changes,
+        );
+
+        let err = parse_parser_description(&grammar.replace("push(Str)", "push(Text)")).unwrap_err();
+        assert_eq!(("name of a declared mode", Some("Tokens".to_string())), (err.expected.as_str(), err.section));
+        assert_eq!((36, 37), (err.line, err.column));
+
+        let err = parse_parser_description(&grammar.replace("mode Raw", "mode Str")).unwrap_err();
+        assert_eq!("new mode name", err.expected);
+    }
+
+    #[test]
+    fn token_payloads() {
+        let grammar = CALCULATOR.replace("Num -> \"(-?)[1-9]([0-9]*)\";", "Num -> \"[0-9]+\" priority 1 : i64 = |s| s.parse::<i64>().map_err(|e| { e });");
+        let tokens = parse_parser_description(&grammar).unwrap().tokens;
+        let payload = TokenPayload { ty: "i64".to_string(), conversion: "|s| s.parse::<i64>().map_err(|e| { e })".to_string() };
+        assert_eq!((1, Some(payload.clone())), (tokens[0].priority, tokens[0].payload.clone()));
+        assert_eq!(None, tokens[1].payload);
+
+        let err = parse_parser_description(&CALCULATOR.replace("Div -> \"/\";", "Div -> \"/\" : = |s| s;")).unwrap_err();
+        assert_eq!(("value type", (33, 18)), (err.expected.as_str(), (err.line, err.column)));
+        let err = parse_parser_description(&CALCULATOR.replace("Div -> \"/\";", "Div -> \"/\" : char;")).unwrap_err();
+        assert_eq!("`=` after value type", err.expected);
+        let err = parse_parser_description(&CALCULATOR.replace("Div -> \"/\";", "Div -> \"/\" : char = ;")).unwrap_err();
+        assert_eq!("conversion expression", err.expected);
+        let quoted = CALCULATOR.replace("Div -> \"/\";", "Div -> \"/\" : String = |s: &str| Ok::<_, ()>(s.trim_matches('\"').replace('}', \"\\'\").replace('\\'', \")\"));");
+        let conversion = "|s: &str| Ok::<_, ()>(s.trim_matches('\"').replace('}', \"\\'\").replace('\\'', \")\"))";
+        assert_eq!(conversion, parse_parser_description(&quoted).unwrap().tokens[4].payload.clone().unwrap().conversion);
+        let unbalanced = CALCULATOR.replace("Div -> \"/\";", "Div -> \"/\" : char = |s| s.parse()); Star -> \"[*]\" : char = |s| s.parse() ;");
+        assert_eq!("|s| s.parse()", parse_parser_description(&unbalanced).unwrap().tokens[5].payload.clone().unwrap().conversion);
+
+        let inherited = grammar.replace("NotTerminals {", "Tokens mode Inner { Num -> \"[0-9]+\"; }\nNotTerminals {");
+        assert_eq!(Some(payload), parse_parser_description(&inherited).unwrap().tokens[8].payload);
+        let conflicting = "Tokens mode Inner { Plus -> \"\\\\+\" : char = |s| s.parse(); }\nNotTerminals {";
+        let err = parse_parser_description(&grammar.replace("NotTerminals {", conflicting)).unwrap_err();
+        assert_eq!("no value type like the first declaration", err.expected);
+        assert_eq!((39, 37), (err.line, err.column));
+    }
+
+    #[test]
+    fn missing_type_separator() {
+        let grammar = CALCULATOR.replace("T {} {res # i64}", "T {} {res i64}");
+        let err = parse_parser_description(&grammar).unwrap_err();
+        assert_eq!(Some("NotTerminals".to_string()), err.section);
+        assert_eq!((53, 18), (err.line, err.column));
+    }
+
+    #[test]
+    fn options_section() {
+        let grammar = format!("Options {{ module = \"calc\"; visibility = \"pub(crate)\"; prefix = \"Calc\"; }}\n{CALCULATOR}");
+        let options = parse_parser_description(&grammar).unwrap().options;
+        assert_eq!(Some("calc".to_string()), options.module);
+        assert_eq!(Some("pub(crate)".to_string()), options.visibility);
+        assert_eq!(Some("Calc".to_string()), options.prefix);
+
+        let err = parse_parser_description(&format!("Options {{ visibility = \"private\"; }}\n{CALCULATOR}")).unwrap_err();
+        assert_eq!(Some("Options".to_string()), err.section);
+        assert_eq!("`pub`, `pub(crate)` or `pub(super)` visibility", err.expected);
+        assert_eq!((1, 24), (err.line, err.column));
+
+        let err = parse_parser_description(&format!("Options {{ prefix = \"1Calc\"; }}\n{CALCULATOR}")).unwrap_err();
+        assert_eq!(("type name prefix", Some("Options".to_string())), (err.expected.as_str(), err.section));
+        assert_eq!((1, 20), (err.line, err.column));
+        let err = parse_parser_description(&format!("Options {{ module = \"calc-1\"; }}\n{CALCULATOR}")).unwrap_err();
+        assert_eq!("module name", err.expected);
+
+        let err = parse_parser_description(&format!("Options {{ name = \"calc\"; }}\n{CALCULATOR}")).unwrap_err();
+        assert_eq!("`module`, `visibility`, `prefix`, `trivia`, `recovery` or `tree` option", err.expected);
+        assert_eq!((1, 11), (err.line, err.column));
+    }
+
+    #[test]
+    fn recovery_option() {
+        let options = parse_parser_description(&format!("Options {{ recovery = \"Rb, Plus\"; }}\n{CALCULATOR}")).unwrap().options;
+        assert_eq!(Some(vec!["Rb".to_string(), "Plus".to_string()]), options.recovery);
+        let options = parse_parser_description(&format!("Options {{ recovery = \"\"; }}\n{CALCULATOR}")).unwrap().options;
+        assert_eq!(Some(Vec::new()), options.recovery);
+
+        let err = parse_parser_description(&format!("Options {{ recovery = \"Rb; Plus\"; }}\n{CALCULATOR}")).unwrap_err();
+        assert_eq!("comma separated token names", err.expected);
+        let err = parse_parser_description(&format!("Options {{ recovery = \"Rb, Semi\"; }}\n{CALCULATOR}")).unwrap_err();
+        assert_eq!(("names of declared tokens", Some("Options".to_string())), (err.expected.as_str(), err.section));
+        assert_eq!((1, 22), (err.line, err.column));
+    }
+
+    #[test]
+    fn trivia_option() {
+        let options = parse_parser_description(&format!("Options {{ trivia = \"trailing\"; }}\n{CALCULATOR}")).unwrap().options;
+        assert_eq!(Some(TriviaAttachment::Trailing), options.trivia);
+        assert_eq!(None, parse_parser_description(CALCULATOR).unwrap().options.trivia);
+
+        let options = parse_parser_description(&format!("Options {{ trivia = \"leading\"; }}\n{CALCULATOR}")).unwrap().options;
+        assert_eq!(Some(TriviaAttachment::Leading), options.trivia);
+
+        let err = parse_parser_description(&format!("Options {{ trivia = \"comments\"; }}\n{CALCULATOR}")).unwrap_err();
+        assert_eq!("`leading` or `trailing` trivia", err.expected);
+        assert_eq!((1, 20), (err.line, err.column));
+        let err = parse_parser_description(&format!("Options {{ trivia = \"Leading\"; }}\n{CALCULATOR}")).unwrap_err();
+        assert_eq!(("`leading` or `trailing` trivia", Some("Options".to_string())), (err.expected.as_str(), err.section));
+    }
+
+    #[test]
+    fn tree_option() {
+        let options = parse_parser_description(&format!("Options {{ tree = \"none\"; }}\n{CALCULATOR}")).unwrap().options;
+        assert_eq!(ParseTree::None, options.tree);
+        assert_eq!(ParseTree::GraphViz, parse_parser_description(CALCULATOR).unwrap().options.tree);
+        let options = parse_parser_description(&format!("Options {{ tree = \"cst\"; }}\n{CALCULATOR}")).unwrap().options;
+        assert_eq!(ParseTree::Cst, options.tree);
+
+        let options = parse_parser_description(&format!("Options {{ tree = \"graph_viz\"; }}\n{CALCULATOR}")).unwrap().options;
+        assert_eq!(ParseTree::GraphViz, options.tree);
+
+        let err = parse_parser_description(&format!("Options {{ tree = \"dom\"; }}\n{CALCULATOR}")).unwrap_err();
+        assert_eq!("`graph_viz`, `cst`, `ast` or `none` tree", err.expected);
+        assert_eq!((1, 18), (err.line, err.column));
+        let err = parse_parser_description(&format!("Options {{ tree = \"graphviz\"; }}\n{CALCULATOR}")).unwrap_err();
+        assert_eq!(("`graph_viz`, `cst`, `ast` or `none` tree", Some("Options".to_string())), (err.expected.as_str(), err.section));
+    }
+
+    #[test]
+    fn member_annotations() {
+        let grammar = CALCULATOR.replace("P {} {res # i64} -> Lb E Rb", "P {} {res # i64} -> Lb! inner = E ^ close:Rb !");
+        let description = parse_parser_description(&grammar).unwrap();
+        let annotations: Vec<Annotation> = description.not_terminal[8].rules[0]
+            .members
+            .iter()
+            .filter_map(|member| match member {
+                RuleMember::RuleCall(token) => Some(token.annotation.clone()),
+                _ => None,
+            })
+            .collect();
+        let dropped = Annotation { drop: true, ..Annotation::default() };
+        let promoted = Annotation { label: Some("inner".to_string()), promote: true, drop: false };
+        let closing = Annotation { label: Some("close".to_string()), ..dropped.clone() };
+        assert_eq!(vec![dropped, promoted, closing], annotations);
+
+        let err = parse_parser_description(&CALCULATOR.replace("-> Lb E Rb", "-> Lb E Rb e=")).unwrap_err();
+        assert_eq!("rule member after label", err.expected);
+        assert_eq!((61, 36), (err.line, err.column));
+    }
+
+    #[test]
+    fn misspelled_section() {
+        let grammar = CALCULATOR.replace("Skip {", "Skp {");
+        let err = parse_parser_description(&grammar).unwrap_err();
+        assert_eq!(Some("Skip".to_string()), err.section);
+        assert_eq!((22, 1), (err.line, err.column));
+    }
+}
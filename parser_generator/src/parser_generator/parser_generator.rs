@@ -1,14 +1,16 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::iter::Map;
 use std::ops::Add;
 use std::slice::Iter;
-use crate::parser_generator::parser_description::{ParserDescription, Rule, RuleMember, RuleToken, Typed};
+use crate::parser_generator::first_follow::{FirstFollow, NotTerminalSets, PredictionRow, PredictionTable};
+use crate::parser_generator::names::Names;
+use crate::parser_generator::ll1_conflict::{Alternative, Conflict, ConflictKind, ConflictReport, EPSILON, FollowChain};
+use crate::parser_generator::action_code::token_results;
+use crate::parser_generator::parser_description::{NotTerminal, ParseTree, ParserDescription, Rule, RuleMember, RuleToken, Typed};
 use crate::parser_generator::tokenizer_generator::EOF_TOKEN;
 
 
-const S: &'static str = "S";
-
-const GET_TOKEN: &'static str = "let token = &self.tokens[self.pointer];";
+pub const S: &str = "S";
 
 
 struct Grammar<'a> {
@@ -17,41 +19,133 @@ struct Grammar<'a> {
     rules: HashMap<&'a str, Rules<'a>>,
 }
 
-const PARSER: &'static str =
+pub const PARSER: &str =
     "
-struct Parser {
-    tokens: Vec<Token>,
+struct $Parser<B> {
+    tokens: Vec<$Token>,
     pointer: usize,
     counter: usize,
+    /// Errors recovered from, only collected with the `recovery` option.
+    errors: Vec<$ParseError>,
+    builder: B,
 }
 
-impl Parser {
-    fn new(tokens: Vec<Token>) -> Parser {
-        Parser{tokens, pointer: 0, counter: 0}
+impl<B: $TreeBuilder> $Parser<B> {
+    fn new(tokens: Vec<$Token>, builder: B) -> $Parser<B> {
+        $Parser{tokens, pointer: 0, counter: 0, errors: Vec::new(), builder}
+    }
+
+    /// Span of the tokens from `start` to the pointer, empty before the current token if none were read.
+    fn span_since(&self, start: usize) -> $Span {
+        let first = self.tokens[start].span();
+        if self.pointer == start {
+            return $Span { end: first.start, ..first };
+        }
+        first.to(self.tokens[self.pointer - 1].span())
+    }
+
+    /// Error at the current token listing the tokens which could be there instead.
+    fn expected(&self, tokens: &[&str]) -> $ParseError {
+        let expected = match tokens {
+            [token] => token.to_string(),
+            _ => format!(\"one of {}\", tokens.join(\", \")),
+        };
+        let found = self.tokens[self.pointer].describe();
+        $ParseError{span: self.tokens[self.pointer].span(), message: format!(\"expected {expected} but found {found}\")}
     }
 }
 
 ";
 
-const EOF_TOKEN_PARSE: &'static str =
+pub const RECOVER: &str =
+    "
+impl<B: $TreeBuilder> $Parser<B> {
+    /// Records the error and skips tokens up to one `sync` accepts or `Eof`. When the last
+    /// error was found at the same token, that token is skipped too so parsing moves on,
+    /// but `Eof` is never skipped, the callers then finish their rules at it and errors
+    /// already recorded there aren't recorded again.
+    fn recover(&mut self, error: $ParseError, sync: impl Fn(&$Token) -> bool) {
+        let is_eof = |token: &$Token| matches!(token, $Token::Eof(..));
+        let repeated = self.errors.last().map_or(false, |last| last.span == error.span);
+        if repeated && !is_eof(&self.tokens[self.pointer]) {
+            self.pointer += 1;
+        } else if !self.errors.iter().any(|old| old.span == error.span && old.message == error.message) {
+            self.errors.push(error);
+        }
+        while !is_eof(&self.tokens[self.pointer]) && !sync(&self.tokens[self.pointer]) {
+            self.pointer += 1;
+        }
+    }
+}
+";
+
+const EOF_TOKEN_PARSE: &str =
     "
-    fn Eof(&mut self) -> Result<(String, GraphVizNode), ParseError> {
-        let token = &self.tokens[self.pointer];
-        let pos = self.pointer;
-        let id = self.counter.to_string();
-        self.pointer += 1;
-        self.counter += 1;
-        match token {
-            Token::Eof => Ok((\"Eof\".to_string(), GraphVizNode::new_leaf(id, \"Eof\".to_string()))),
-            _          => Err(ParseError{position: pos, message: \"Expected eof\".to_string()})
+    fn Eof(&mut self) -> Result<(B::Node, $Span, (String)), $ParseError> {
+        let id = self.counter;
+        match &self.tokens[self.pointer] {
+            $Token::Eof(span, ..) => {
+                let span = *span;
+                self.pointer += 1;
+                self.counter += 1;
+                Ok((self.builder.leaf(id, \"Eof\", span), span, \"\".to_string()))
+            }
+            _ => Err(self.expected(&[\"Eof\"])),
         }
     }
 ";
 
+pub const PARSER_IMPL: &str = "impl<B: $TreeBuilder> $Parser<B> {\n";
+
 type Rules<'a> = Vec<Vec<&'a str>>;
 
-pub fn generate_parser(parser_description: &ParserDescription) -> String {
+pub fn generate_parser(parser_description: &ParserDescription) -> Result<String, ConflictReport> {
+    check_ll1(parser_description)?;
+    let grammar = get_grammar(parser_description);
+    let names = Names::new(&parser_description.options);
+
+    let first = generate_first(&grammar);
+    let follow = generate_follow(&grammar, &first);
+
+    let mut answer = String::new();
+    answer.push_str(&names.fill(PARSER));
+    answer.push_str(&names.fill(PARSER_IMPL));
+
+    answer.push_str(&generate_tokens_parse(&grammar, &get_return_map(parser_description), &names));
+
+    let rules = generate_rules(
+        &grammar,
+        parser_description,
+        &first,
+        &follow,
+        &names,
+    );
+
+    answer.push_str(&rules);
+    answer.push('}');
+    if parser_description.options.recovery.is_some() {
+        answer.push_str(&names.fill(RECOVER));
+    }
+
+    answer.push_str(&generate_parse_func(parser_description, &names));
+
+    Ok(answer)
+}
+
+/// Fails with every LL(1) conflict of the grammar.
+pub fn check_ll1(parser_description: &ParserDescription) -> Result<(), ConflictReport> {
+    let grammar = get_grammar(parser_description);
+    let first = generate_first(&grammar);
+    let follow = generate_follow(&grammar, &first);
+
+    let conflicts = find_conflicts(&first, &follow, &grammar, parser_description);
+    match conflicts.is_empty() {
+        true => Ok(()),
+        false => Err(ConflictReport { conflicts }),
+    }
+}
 
+fn get_grammar(parser_description: &ParserDescription) -> Grammar<'_> {
     let not_terminals: HashSet<&str> = parser_description
         .not_terminal
         .iter()
@@ -67,88 +161,206 @@ pub fn generate_parser(parser_description: &ParserDescription) -> String {
         .map(|x| x.name.as_str())
         .collect();
 
-
-
     let rules: HashMap<&str, Rules> = parser_description
         .not_terminal
         .iter()
         .map(|not_term| (not_term.name.as_str(), get_rules(&not_term.rules)))
         .collect();
 
-    let grammar = Grammar { terminal: terminals, not_terminal: not_terminals, rules };
+    Grammar { terminal: terminals, not_terminal: not_terminals, rules }
+}
 
+/// FIRST and FOLLOW sets of every not terminal in declaration order.
+pub fn get_first_follow(parser_description: &ParserDescription) -> FirstFollow {
+    let grammar = get_grammar(parser_description);
     let first = generate_first(&grammar);
     let follow = generate_follow(&grammar, &first);
 
+    let sets = parser_description
+        .not_terminal
+        .iter()
+        .map(|not_term| {
+            let name = not_term.name.as_str();
+            NotTerminalSets {
+                not_terminal: not_term.name.clone(),
+                nullable: first[name].contains(""),
+                first: get_sorted_tokens(first[name].iter()),
+                follow: get_sorted_tokens(follow[name].iter()),
+            }
+        })
+        .collect();
+    FirstFollow { sets }
+}
 
-    if !validate_first_and_follow(&first, &follow, &grammar) {
-        panic!("Not LL(1) grammar")
-    }
-
-
-    let mut answer = String::new();
-    answer.push_str(PARSER);
-    answer.push_str("impl Parser {\n");
-
-    answer.push_str(&generate_tokens_parse(&grammar));
-
-    let rules = generate_rules(
-        &grammar,
-        &parser_description,
-        &first,
-        &follow,
-    );
+/// Rules of every not terminal predicted at every lookahead token, not terminals in declaration order.
+pub fn get_prediction_table(parser_description: &ParserDescription) -> PredictionTable {
+    let grammar = get_grammar(parser_description);
+    let first = generate_first(&grammar);
+    let follow = generate_follow(&grammar, &first);
 
-    answer.push_str(&rules);
-    answer.push_str("}");
+    let mut tokens: Vec<String> = Vec::new();
+    for token in &parser_description.tokens {
+        if !tokens.contains(&token.name) {
+            tokens.push(token.name.clone());
+        }
+    }
+    tokens.push(EOF_TOKEN.to_string());
 
-    answer.push_str(&generate_parse_func(&parser_description));
+    let rows = parser_description
+        .not_terminal
+        .iter()
+        .map(|not_term| {
+            let name = not_term.name.as_str();
+            let rules = &grammar.rules[name];
+            let mut cells = vec![Vec::new(); tokens.len()];
+            for (i, rule) in rules.iter().enumerate() {
+                for token in get_lookahead(rule, name, &grammar, &first, &follow) {
+                    let column = tokens.iter().position(|t| t == token).unwrap();
+                    cells[column].push(i);
+                }
+            }
+            PredictionRow {
+                not_terminal: not_term.name.clone(),
+                rules: rules.iter().map(|rule| get_rule_text(rule)).collect(),
+                cells,
+            }
+        })
+        .collect();
+    PredictionTable { tokens, rows }
+}
 
-    answer
+/// Tokens predicting the rule: its FIRST set, with FOLLOW of the not terminal when the rule can be empty.
+fn get_lookahead<'a>(
+    rule: &[&'a str],
+    not_terminal: &str,
+    grammar: &Grammar,
+    first: &HashMap<&'a str, HashSet<&'a str>>,
+    follow: &HashMap<&'a str, HashSet<&'a str>>,
+) -> HashSet<&'a str> {
+    let mut rule_first = get_first(rule, grammar, first);
+    if rule_first.remove("") {
+        rule_first.extend(&follow[not_terminal])
+    }
+    rule_first
 }
 
-fn generate_parse_func(parser_description: &ParserDescription) -> String {
-    let mut answer = String::new();
+/// `parse_with` building the tree with any `$TreeBuilder` and `parse` building the tree of the
+/// `tree` option, or only returning the returns without a tree. With the `cst` and `ast` trees the node of `S`
+/// is the last return of `parse_with` and takes the place of the tree in `parse`. With the `recovery` option
+/// `parse_recovering_with` and `parse_recovering` also return all the errors recovered from.
+pub fn generate_parse_func(parser_description: &ParserDescription, names: &Names) -> String {
     let start = parser_description.not_terminal.iter().find(|s| s.name == "S").unwrap();
+    let all_types = start.returns.iter().map(|t| t.ty.as_str()).collect::<Vec<&str>>().join(",");
+    let input = Typed { name: "input".to_string(), ty: "String".to_string() };
+    let builder = Typed { name: "builder".to_string(), ty: "&mut B".to_string() };
+    let mut input_args = start.args.clone();
+    input_args.insert(0, input);
+    let mut builder_args = input_args.clone();
+    builder_args.insert(1, builder);
+    let args = get_tuple("", &start.args);
+    let (input_types, builder_types) = (get_input_args_without_self(&input_args), get_input_args_without_self(&builder_args));
+
+    let (tree, default_builder, map, return_types) = match parser_description.options.tree {
+        ParseTree::GraphViz => ("GraphVizNode, ".to_string(), names.graph_viz_builder.as_str(), "|result| result".to_string(), all_types.clone()),
+        ParseTree::None => (String::new(), names.no_tree.as_str(), "|result| result.1".to_string(), all_types.clone()),
+        ParseTree::Cst | ParseTree::Ast => {
+            // The node is the last return, `parse` returns it in place of the tree.
+            let (cst, returns) = start.returns.split_last().expect("syntax trees are returned");
+            let map = format!("|(_, {})| ({}, {})", get_tuple("", &start.returns), cst.name, get_tuple("", returns));
+            let return_types = returns.iter().map(|t| t.ty.as_str()).collect::<Vec<&str>>().join(",");
+            (format!("{}, ", cst.ty), names.no_tree.as_str(), map, return_types)
+        }
+    };
+    let mut call = builder_args.iter().map(|arg| arg.name.clone()).collect::<Vec<String>>();
+    call[1] = format!("&mut {default_builder}");
+    let call = format!("({})", call.join(", "));
+
+    let template = match parser_description.options.recovery {
+        None => format!("
+/// Parses the input building the tree with `builder`.
+$vis fn parse_with<B: $TreeBuilder>{builder_types} -> Result<(B::Node, ({all_types})), $ParseError> {{
+    $Parser::new($Tokenizer::tokenize(input)?, builder).S{args}.map(|(tree, _, res)| (tree, res))
+}}
+
+$vis fn parse{input_types} -> Result<({tree}({return_types})), $ParseError> {{
+    parse_with{call}.map({map})
+}}
+"),
+        Some(_) => format!("
+/// Parses the input collecting every error recovered from, building the tree with `builder`.
+/// There is no tree when the input can't be tokenized or parsing couldn't recover before `Eof`,
+/// otherwise it's partial when errors were recovered from.
+$vis fn parse_recovering_with<B: $TreeBuilder>{builder_types} -> (Option<(B::Node, ({all_types}))>, Vec<$ParseError>) {{
+    let tokens = match $Tokenizer::tokenize(input) {{
+        Ok(tokens) => tokens,
+        Err(error) => return (None, vec![error]),
+    }};
+    let mut parser = $Parser::new(tokens, builder);
+    let result = parser.S{args}.map(|(tree, _, res)| (tree, res));
+    let mut errors = std::mem::take(&mut parser.errors);
+    match result {{
+        Ok(result) => (Some(result), errors),
+        Err(error) => {{
+            errors.push(error);
+            (None, errors)
+        }}
+    }}
+}}
+
+/// Parses the input collecting every error recovered from.
+$vis fn parse_recovering{input_types} -> (Option<({tree}({return_types}))>, Vec<$ParseError>) {{
+    let (result, errors) = parse_recovering_with{call};
+    (result.map({map}), errors)
+}}
+
+$vis fn parse{input_types} -> Result<({tree}({return_types})), $ParseError> {{
+    match parse_recovering{} {{
+        (Some(result), errors) if errors.is_empty() => Ok(result),
+        (_, mut errors) => Err(errors.remove(0)),
+    }}
+}}
+", get_tuple("", &input_args)),
+    };
+    names.fill(&template)
+}
 
-    let return_type = get_return_type(&start.returns);
-    let input = Typed{name: "input".to_string(), ty: "String".to_string()};
-    let mut all_input_args = start.args.clone();
-    all_input_args.insert(0, input);
-
-    let input_types = get_input_args_without_self(&all_input_args);
-    let signature = format!("pub fn parse{input_types} -> {return_type}");
-    let parser_input = get_tuple("", &start.args);
-    let parsing = format!("Parser::new(Tokenizer::tokenize(input)?).S{parser_input}");
-    let func = format!("{signature} {{ {parsing} }}");
-    answer.push_str(&func);
-    answer
-
+/// Functions of the parser matching a single token.
+pub fn generate_token_functions(parser_description: &ParserDescription, names: &Names) -> String {
+    let grammar = get_grammar(parser_description);
+    generate_tokens_parse(&grammar, &get_return_map(parser_description), names)
 }
 
-fn generate_tokens_parse(grammar: &Grammar) -> String {
+fn generate_tokens_parse(grammar: &Grammar, return_map: &HashMap<&str, Vec<Typed>>, names: &Names) -> String {
     let mut answer = String::new();
     for terminal in &grammar.terminal {
-        answer.push_str(&generate_token_parse(terminal))
+        answer.push_str(&generate_token_parse(terminal, &return_map[terminal], names))
     }
-    answer.push_str(EOF_TOKEN_PARSE);
+    answer.push_str(&names.fill(EOF_TOKEN_PARSE));
     answer
 }
 
-fn generate_token_parse(terminal: &str) -> String {
-    format!("
-    fn {}(&mut self) -> Result<(GraphVizNode, (String)), ParseError> {{
-        let token = &self.tokens[self.pointer];
-        let pos = self.pointer;
-        let id = self.counter.to_string();
-        self.pointer += 1;
-        self.counter += 1;
-        match token {{
-            Token::{}(s) => Ok((GraphVizNode::new_leaf(id, \"{}\".to_string()), s.clone())),
-            _ => Err(ParseError{{position: pos, message: \"Expected {}\".to_string()}}),
+/// Terminal function returning the token value, and its trivia when the token results have them.
+fn generate_token_parse(terminal: &str, results: &[Typed], names: &Names) -> String {
+    let results_type = format!("({})", results.iter().map(|t| t.ty.as_str()).collect::<Vec<&str>>().join(", "));
+    let (fields, value) = match results.len() {
+        1 => ("s, span, ..", "s.clone()"),
+        _ => ("s, span, trivia, ..", "(s.clone(), trivia.clone())"),
+    };
+    let template = format!("
+    fn {}(&mut self) -> Result<(B::Node, $Span, {results_type}), $ParseError> {{
+        let id = self.counter;
+        match &self.tokens[self.pointer] {{
+            $Token::{}({fields}) => {{
+                let (span, value) = (*span, {value});
+                self.pointer += 1;
+                self.counter += 1;
+                Ok((self.builder.leaf(id, \"{}\", span), span, value))
+            }}
+            _ => Err(self.expected(&[\"{}\"])),
         }}
     }}
-   ", terminal, terminal, terminal, terminal)
+   ", terminal, terminal, terminal, terminal);
+    names.fill(&template)
 }
 
 fn generate_rules(
@@ -156,6 +368,7 @@ fn generate_rules(
     parser_description: &ParserDescription,
     first: &HashMap<&str, HashSet<&str>>,
     follow: &HashMap<&str, HashSet<&str>>,
+    names: &Names,
 ) -> String {
     let mut answer = String::new();
     let not_terminals = &parser_description.not_terminal;
@@ -166,34 +379,39 @@ fn generate_rules(
         let name = not_term.name.clone();
 
         let input_types = get_input_args_with_self(&not_term.args);
-        let return_type = get_return_type(&not_term.returns);
-        let signature = format!("fn {name}{input_types} -> {return_type}");
+        let return_type = get_return_type(&not_term.returns, names);
+        let signature = match &parser_description.options.recovery {
+            Some(sync) => {
+                answer.push_str(&get_recovering_rule(not_term, &follow[name.as_str()], sync, names));
+                format!("fn {name}_rule{input_types} -> {return_type}")
+            }
+            None => format!("fn {name}{input_types} -> {return_type}"),
+        };
 
         answer.push_str(&signature);
         answer.push_str(" {\n");
 
-        answer.push_str(&get_return_init(&not_term.returns));
+        answer.push_str(&get_return_init(&not_term.args, &not_term.returns));
         answer.push('\n');
 
-        answer.push_str("let id = self.counter.to_string();\n");
-        answer.push_str("let mut children: Vec<GraphVizNode> = Vec::new();\n");
+        answer.push_str("let id = self.counter;\n");
+        answer.push_str("let span_start = self.pointer;\n");
+        answer.push_str("let mut children: Vec<B::Node> = Vec::new();\n");
         answer.push_str("self.counter += 1;\n");
         answer.push_str("let token = &self.tokens[self.pointer];\n");
         answer.push_str("match token {\n");
 
+        let mut expected: HashSet<&str> = HashSet::new();
         for rule in &not_term.rules {
             let str_rule = convert_rule_to_strings(rule);
-            let mut rule_first = get_first(&str_rule, grammar, first);
-            if rule_first.contains("") {
-                rule_first.extend(&follow[&name.as_str()])
-            }
-            rule_first.remove("");
+            let rule_first = get_lookahead(&str_rule, &name, grammar, first, follow);
 
             if rule_first.is_empty() {
                 continue;
             }
 
-            let token_case = get_tokens_for_match(&rule_first);
+            let token_case = get_tokens_for_match(&rule_first, names);
+            expected.extend(&rule_first);
 
             answer.push_str(&token_case);
             answer.push_str("=> {\n");
@@ -203,18 +421,61 @@ fn generate_rules(
             answer.push_str("}\n");
         }
 
-        answer.push_str("_ => return Err(ParseError{position: self.pointer, message: \"Can't match rule\".to_string()})\n");
+        let expected = get_expected_tokens(&expected, parser_description)
+            .iter()
+            .map(|token| format!("\"{token}\""))
+            .collect::<Vec<String>>()
+            .join(", ");
+        answer.push_str(&format!("_ => return Err(self.expected(&[{expected}]))\n"));
         answer.push_str("}\n");
-        let graph_viz = format!("GraphVizNode::new_node(id, \"{name}\".to_string(), children)");
         let tuple = get_tuple("", &not_term.returns);
-        let return_line = format!("Ok(({graph_viz},{tuple}))\n");
-        answer.push_str(&return_line);
+        answer.push_str("let node_span = self.span_since(span_start);\n");
+        answer.push_str(&format!("Ok((self.builder.node(id, \"{name}\", node_span, children), node_span, {tuple}))\n"));
         answer.push_str("}\n\n");
     }
 
     answer
 }
 
+/// `fn {name}` calling `{name}_rule` and recovering from its errors at the FOLLOW set of the not terminal
+/// or at a `sync` token, the node of the not terminal is then an `error` leaf and its returns have default values.
+fn get_recovering_rule(not_term: &NotTerminal, follow: &HashSet<&str>, sync: &[String], names: &Names) -> String {
+    let name = &not_term.name;
+    let input_types = get_input_args_with_self(&not_term.args);
+    let return_type = get_return_type(&not_term.returns, names);
+    let args = get_tuple("", &not_term.args);
+
+    let sync_match = get_sync_match(follow, sync, names);
+    let defaults = format!("({})", not_term.returns.iter().map(|_| "Default::default()").collect::<Vec<&str>>().join(","));
+
+    format!("fn {name}{input_types} -> {return_type} {{
+        let span_start = self.pointer;
+        match self.{name}_rule{args} {{
+            Ok(result) => Ok(result),
+            Err(error) => {{
+                self.recover(error, |token| {sync_match});
+                let id = self.counter;
+                self.counter += 1;
+                let span = self.span_since(span_start);
+                Ok((self.builder.leaf(id, \"error\", span), span, {defaults}))
+            }}
+        }}
+    }}
+
+")
+}
+
+/// Condition on `token` accepting the tokens of FOLLOW, but `Eof`, and the `sync` tokens.
+pub fn get_sync_match(follow: &HashSet<&str>, sync: &[String], names: &Names) -> String {
+    let mut sync_tokens: Vec<&str> = follow.iter().copied().filter(|token| *token != EOF_TOKEN).collect();
+    sync_tokens.extend(sync.iter().map(String::as_str).filter(|token| !follow.contains(token)));
+    sync_tokens.sort();
+    match sync_tokens.is_empty() {
+        true => "false".to_string(),
+        false => format!("matches!(token, {})", get_tokens_for_match(&sync_tokens, names)),
+    }
+}
+
 fn get_parsing_rule(rule: &Rule, return_map: &HashMap<&str, Vec<Typed>>) -> String{
     let mut answer: String = String::new();
 
@@ -222,26 +483,27 @@ fn get_parsing_rule(rule: &Rule, return_map: &HashMap<&str, Vec<Typed>>) -> Stri
     for i in 0 .. rule.members.len() {
         let rule_member = &rule.members[i];
         let s = match rule_member {
-            RuleMember::RuleCall(RuleToken{name, args}) => {
+            RuleMember::RuleCall(RuleToken{name, args, ..}) => {
                 let ident_prefix = name.clone().add(&format!("{i}_"));
                 let call = format!("self.{name}{args}?;");
                 let tuple = get_tuple(&ident_prefix, &return_map[name.as_str()]);
-                let assignment = format!("let (child, {tuple}) = {call}");
-                let child_push = format!("children.push(child);");
+                let assignment = format!("let (child, {ident_prefix}span, {tuple}) = {call}");
+                let child_push = "children.push(child);".to_string();
                 assignment
                     .add("\n")
                     .add(&child_push)
                     .add("\n")
             }
             RuleMember::Command(command) => command.to_string()
-                .add("\n")
+                .add("\n"),
+            RuleMember::Group(_) => unreachable!("groups are desugared before parser generation"),
         };
         answer.push_str(&s);
     }
     answer
 }
 
-fn get_tuple(prefix: &str, idents: &Vec<Typed>) -> String {
+pub fn get_tuple(prefix: &str, idents: &[Typed]) -> String {
     let tuple = idents
         .iter()
         .map(
@@ -253,25 +515,29 @@ fn get_tuple(prefix: &str, idents: &Vec<Typed>) -> String {
     format!("({tuple})")
 }
 
-fn get_tokens_for_match(rule_first: &HashSet<&str>) -> String {
+/// Tokens in declaration order with `Eof` last.
+fn get_expected_tokens<'a>(tokens: &HashSet<&'a str>, parser_description: &ParserDescription) -> Vec<&'a str> {
+    let order = |token: &str| {
+        parser_description.tokens.iter().position(|t| t.name == token).unwrap_or(parser_description.tokens.len())
+    };
+    let mut tokens: Vec<&str> = tokens.iter().copied().collect();
+    tokens.sort_by_key(|token| order(token));
+    tokens
+}
+
+fn get_tokens_for_match<'a>(rule_first: impl IntoIterator<Item = &'a &'a str>, names: &Names) -> String {
     rule_first
-        .iter()
-        .map(
-            |s| {
-                if s == &EOF_TOKEN {
-                    return format!("Token::{EOF_TOKEN}");
-                }
-                return format!("Token::{s}(_)");
-            }
-        ).collect::<Vec<String>>().join("|")
+        .into_iter()
+        .map(|s| format!("{}::{s}(..)", names.token))
+        .collect::<Vec<String>>()
+        .join("|")
 }
 
 fn get_return_map(parser_description: &ParserDescription) -> HashMap<&str, Vec<Typed>> {
     let mut map = HashMap::new();
     let term = &parser_description.tokens;
-    let ident = vec!(Typed { name: "ident".to_string(), ty: "String".to_string() });
     for t in term {
-        map.insert(t.name.as_str(), ident.clone());
+        map.insert(t.name.as_str(), token_results(t, &parser_description.options));
     }
 
     let not_term = &parser_description.not_terminal;
@@ -281,30 +547,36 @@ fn get_return_map(parser_description: &ParserDescription) -> HashMap<&str, Vec<T
     map
 }
 
-fn get_return_init(args: &Vec<Typed>) -> String {
-    get_typed_strings(args)
-        .fold(String::new(), |mut acc, s| {
+/// Declares returns, a return named as an argument starts with the argument value.
+fn get_return_init(args: &[Typed], returns: &[Typed]) -> String {
+    get_typed_strings(returns)
+        .zip(returns)
+        .fold(String::new(), |mut acc, (s, Typed { name, .. })| {
             acc.push_str("let mut ");
             acc.push_str(&s);
+            if args.iter().any(|arg| &arg.name == name) {
+                acc.push_str(" = ");
+                acc.push_str(name);
+            }
             acc.push(';');
             acc
         },
         )
 }
 
-fn get_typed_strings(args: &Vec<Typed>) -> Map<Iter<Typed>, fn(&Typed) -> String> {
+fn get_typed_strings(args: &[Typed]) -> Map<Iter<'_, Typed>, fn(&Typed) -> String> {
     args.iter()
         .map(|Typed { name, ty }| {
             let mut s = String::with_capacity(name.len() + ty.len() + 1);
-            s.push_str(&name);
+            s.push_str(name);
             s.push(':');
-            s.push_str(&ty);
+            s.push_str(ty);
             s
         }
         )
 }
 
-fn get_input_args_with_prefix(args: &Vec<Typed>, prefix: &str) -> String {
+fn get_input_args_with_prefix(args: &[Typed], prefix: &str) -> String {
     let mut input_types =
         get_typed_strings(args)
             .fold(prefix.to_string(), |mut acc, s| {
@@ -318,15 +590,16 @@ fn get_input_args_with_prefix(args: &Vec<Typed>, prefix: &str) -> String {
     input_types
 }
 
-fn get_input_args_without_self(args: &Vec<Typed>) -> String {
+fn get_input_args_without_self(args: &[Typed]) -> String {
     get_input_args_with_prefix(args, "(")
 }
 
-fn get_input_args_with_self(args: &Vec<Typed>) -> String {
+pub fn get_input_args_with_self(args: &[Typed]) -> String {
     get_input_args_with_prefix(args, "(&mut self,")
 }
 
-fn get_return_type(tuple_args: &Vec<Typed>) -> String {
+/// Result of a not terminal function, the node, the span and the returns.
+pub fn get_return_type(tuple_args: &[Typed], names: &Names) -> String {
     let return_types = tuple_args
         .iter()
         .map(|t| t.ty.clone())
@@ -334,20 +607,25 @@ fn get_return_type(tuple_args: &Vec<Typed>) -> String {
         .join(",");
 
 
-    let mut return_type = "Result<(GraphVizNode, ".to_string();
+    let mut return_type = format!("Result<(B::Node, {}, ", names.span);
 
     return_type.push_str(&format!("({return_types})"));
     return_type.push(')');
-    return_type.push_str(",ParseError>");
+    return_type.push(',');
+    return_type.push_str(&names.parse_error);
+    return_type.push('>');
     return_type
 }
 
-fn validate_first_and_follow(
+fn find_conflicts(
     first: &HashMap<&str, HashSet<&str>>,
     follow: &HashMap<&str, HashSet<&str>>,
     grammar: &Grammar,
-) -> bool {
-    for a in &grammar.not_terminal {
+    parser_description: &ParserDescription,
+) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    for not_term in &parser_description.not_terminal {
+        let a = not_term.name.as_str();
         let a_follow = &follow[a];
         let rules = &grammar.rules[a];
         for i in 0..rules.len() {
@@ -366,21 +644,130 @@ fn validate_first_and_follow(
                     first,
                 );
 
+                let alpha = Alternative { index: i, text: get_rule_text(alpha) };
+                let beta = Alternative { index: j, text: get_rule_text(beta) };
+
                 if !first_alpha.is_disjoint(&first_beta) {
-                    return false;
+                    conflicts.push(Conflict {
+                        kind: ConflictKind::FirstFirst,
+                        not_terminal: a.to_string(),
+                        first: alpha.clone(),
+                        second: beta.clone(),
+                        tokens: get_sorted_tokens(first_alpha.intersection(&first_beta)),
+                        follow_chains: Vec::new(),
+                    });
                 }
 
                 if first_alpha.contains("") && !a_follow.is_disjoint(&first_beta) {
-                    return false;
+                    conflicts.push(get_first_follow_conflict(a, alpha.clone(), beta.clone(), &first_beta, grammar, first, follow, parser_description));
                 }
 
                 if first_beta.contains("") && !a_follow.is_disjoint(&first_alpha) {
-                    return false;
+                    conflicts.push(get_first_follow_conflict(a, beta, alpha, &first_alpha, grammar, first, follow, parser_description));
                 }
             }
         }
     }
-    true
+    conflicts
+}
+
+#[allow(clippy::too_many_arguments)]
+fn get_first_follow_conflict(
+    a: &str,
+    nullable: Alternative,
+    other: Alternative,
+    other_first: &HashSet<&str>,
+    grammar: &Grammar,
+    first: &HashMap<&str, HashSet<&str>>,
+    follow: &HashMap<&str, HashSet<&str>>,
+    parser_description: &ParserDescription,
+) -> Conflict {
+    let tokens = get_sorted_tokens(follow[a].intersection(other_first));
+    let follow_chains = tokens
+        .iter()
+        .map(|token| FollowChain {
+            token: token.clone(),
+            rules: get_follow_chain(a, token, grammar, first, follow, parser_description),
+        })
+        .collect();
+
+    Conflict {
+        kind: ConflictKind::FirstFollow,
+        not_terminal: a.to_string(),
+        first: nullable,
+        second: other,
+        tokens,
+        follow_chains,
+    }
+}
+
+/// Finds the shortest sequence of rules which puts `token` into FOLLOW(`a`).
+fn get_follow_chain<'a>(
+    a: &'a str,
+    token: &str,
+    grammar: &Grammar<'a>,
+    first: &HashMap<&'a str, HashSet<&'a str>>,
+    follow: &HashMap<&'a str, HashSet<&'a str>>,
+    parser_description: &'a ParserDescription,
+) -> Vec<String> {
+    let mut parents: HashMap<&str, (&str, String)> = HashMap::new();
+    let mut visited = HashSet::from([a]);
+    let mut queue = VecDeque::from([a]);
+
+    while let Some(x) = queue.pop_front() {
+        let mut found = None;
+        'search: for not_term in &parser_description.not_terminal {
+            let b = not_term.name.as_str();
+            for rule in &grammar.rules[b] {
+                for i in 0..rule.len() {
+                    if rule[i] != x {
+                        continue;
+                    }
+                    let first_rest = get_first(&rule[i + 1..], grammar, first);
+                    let rule_text = format!("{b} -> {}", get_rule_text(rule));
+                    if first_rest.contains(token) {
+                        found = Some(format!("`{rule_text}`: {token} can follow {x}"));
+                        break 'search;
+                    }
+                    if first_rest.contains("") && follow[b].contains(token) && visited.insert(b) {
+                        parents.insert(b, (x, format!("`{rule_text}`: FOLLOW({b}) is in FOLLOW({x})")));
+                        queue.push_back(b);
+                    }
+                }
+            }
+        }
+
+        if found.is_none() && x == S && token == EOF_TOKEN {
+            found = Some(format!("{S} is the start not terminal, followed by {EOF_TOKEN}"));
+        }
+
+        if let Some(last) = found {
+            let mut chain = vec![last];
+            let mut current = x;
+            while let Some((child, rule_text)) = parents.get(current) {
+                chain.push(rule_text.clone());
+                current = child;
+            }
+            chain.reverse();
+            return chain;
+        }
+    }
+    Vec::new()
+}
+
+fn get_rule_text(rule: &[&str]) -> String {
+    if rule.is_empty() {
+        return EPSILON.to_string();
+    }
+    rule.join(" ")
+}
+
+fn get_sorted_tokens<'a>(tokens: impl Iterator<Item = &'a &'a str>) -> Vec<String> {
+    let mut tokens: Vec<String> = tokens
+        .map(|s| if s.is_empty() { EPSILON.to_string() } else { s.to_string() })
+        .collect();
+    tokens.sort();
+    tokens
 }
 
 
@@ -428,7 +815,7 @@ fn get_first<'a>(rule: &[&'a str], grammar: &Grammar, not_terminal_first: &HashM
 
     let mut first = not_terminal_first[first_token].clone();
 
-    if first.contains("") {
+    if first.remove("") {
         first.extend(get_first(&rule[1..], grammar, not_terminal_first))
     }
 
@@ -487,22 +874,100 @@ fn generate_follow<'a>(grammar: &Grammar<'a>, first: &HashMap<&'a str, HashSet<&
     follow
 }
 
-fn get_rules(rules: &Vec<Rule>) -> Rules {
+fn get_rules(rules: &[Rule]) -> Rules<'_> {
     rules.iter()
         .map(convert_rule_to_strings)
         .collect()
 }
 
 fn convert_rule_to_strings(rule: &Rule) -> Vec<&str> {
-    match rule {
-        Rule { members } =>
-            members
-                .iter()
-                .filter_map(|x| match x {
-                    RuleMember::RuleCall(RuleToken { name, .. }) => Some(name.as_str()),
-                    RuleMember::Command(_) => None
-                }
-                )
-                .collect()
+    rule.members
+        .iter()
+        .filter_map(|x| match x {
+            RuleMember::RuleCall(RuleToken { name, .. }) => Some(name.as_str()),
+            RuleMember::Command(_) => None,
+            RuleMember::Group(_) => unreachable!("groups are desugared before parser generation"),
+        }
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_generator::ll1_conflict::ConflictKind;
+    use crate::parser_generator::parser_description::parse_parser_description;
+    use super::{generate_parser, get_first_follow};
+
+    const DANGLING_ELSE: &str = r#"
+Prelude {}
+Skip { " "; }
+Tokens {
+    If -> "if";
+    Then -> "then";
+    Else -> "else";
+    Cond -> "c";
+    Act -> "a";
+}
+NotTerminals {
+    S {} {} -> St;
+    St {} {} -> If Cond Then St El | If Cond Then Act | Act;
+    El {} {} -> Else St | ;
+}
+"#;
+
+    #[test]
+    fn calculator_has_no_conflicts() {
+        let grammar = include_str!("../../resources/calculator.txt");
+        let description = parse_parser_description(grammar).unwrap();
+        assert!(generate_parser(&description).is_ok());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn errors_list_expected_tokens_in_declaration_order() {
+        let grammar = include_str!("../../resources/calculator.txt");
+        let description = parse_parser_description(grammar).unwrap();
+        let code = generate_parser(&description).unwrap();
+        assert!(code.contains("_ => return Err(self.expected(&[\"Num\", \"Minus\", \"Lb\"]))"));
+        assert!(code.contains("_ => return Err(self.expected(&[\"Plus\", \"Minus\", \"Comb\", \"Rb\", \"Eof\"]))"));
+    }
+
+    #[test]
+    fn nullable_prefix_keeps_the_rule_not_nullable() {
+        let grammar = "Prelude {} Skip {} Tokens { Num -> \"[0-9]+\"; Plus -> \"\\\\+\"; Minus -> \"-\"; } \
+                       NotTerminals { S {} {} -> X Num; X {} {} -> Y Plus | Num; Y {} {} -> Minus | ; }";
+        let description = parse_parser_description(grammar).unwrap();
+        assert!(generate_parser(&description).is_ok());
+    }
+
+    #[test]
+    fn reports_all_conflicts() {
+        let description = parse_parser_description(DANGLING_ELSE).unwrap();
+        let conflicts = generate_parser(&description).unwrap_err().conflicts;
+        assert_eq!(2, conflicts.len());
+
+        let first_first = &conflicts[0];
+        assert_eq!(ConflictKind::FirstFirst, first_first.kind);
+        assert_eq!("St", first_first.not_terminal);
+        assert_eq!((0, 1), (first_first.first.index, first_first.second.index));
+        assert_eq!("If Cond Then St El", first_first.first.text);
+        assert_eq!(vec!["If".to_string()], first_first.tokens);
+
+        let first_follow = &conflicts[1];
+        assert_eq!(ConflictKind::FirstFollow, first_follow.kind);
+        assert_eq!("El", first_follow.not_terminal);
+        assert_eq!((1, 0), (first_follow.first.index, first_follow.second.index));
+        assert_eq!(vec!["Else".to_string()], first_follow.tokens);
+        assert_eq!(1, first_follow.follow_chains.len());
+        assert_eq!(2, first_follow.follow_chains[0].rules.len());
+    }
+
+    #[test]
+    fn first_follow_sets_in_declaration_order() {
+        let description = parse_parser_description(DANGLING_ELSE).unwrap();
+        let sets = get_first_follow(&description).sets;
+        assert_eq!(vec!["S", "St", "El"], sets.iter().map(|s| s.not_terminal.as_str()).collect::<Vec<_>>());
+        assert_eq!(vec!["Act", "If"], sets[1].first);
+        assert_eq!(vec!["Else", "ε"], sets[2].first);
+        assert_eq!(vec!["Else", "Eof"], sets[2].follow);
+    }
+}
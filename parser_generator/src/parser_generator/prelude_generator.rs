@@ -1,14 +1,20 @@
 use std::ops::Add;
-use crate::parser_generator::parser_description::ParserDescription;
+use crate::parser_generator::parser_description::{ParseTree, ParserDescription};
 
-const DEFUALT_PREFIX: &'static str = "
+const DEFUALT_PREFIX: &str = "
 #![allow(warnings, unused, non_snake_case, non_camel_case_types)]
 use regex::Regex;
+use regex_automata::{meta, Anchored, Input, MatchKind, PatternSet};
 use lazy_static::lazy_static;
-use graph_viz::GraphVizNode;
 ";
 
+/// Only parsers building the `GraphVizNode` tree depend on `graph_viz`.
+const GRAPH_VIZ_IMPORT: &str = "use graph_viz::GraphVizNode;\n";
+
 pub fn generate_prelude(parser_description: &ParserDescription) -> String {
-    let answer = DEFUALT_PREFIX.to_string();
+    let mut answer = DEFUALT_PREFIX.to_string();
+    if parser_description.options.tree == ParseTree::GraphViz {
+        answer.push_str(GRAPH_VIZ_IMPORT);
+    }
     answer.add(&parser_description.prelude)
 }
\ No newline at end of file
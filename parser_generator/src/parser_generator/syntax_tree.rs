@@ -0,0 +1,526 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::parser_generator::action_code::{member_prefix, token_results};
+use crate::parser_generator::names::Names;
+use crate::parser_generator::parser_description::{
+    NotTerminal, ParseTree, ParserDescription, Rule, RuleMember, RuleToken, Typed,
+};
+
+const LEAF: &str = "
+/// Token of the syntax tree with its text, or its value when the token declares a value type.
+#[derive(Debug, Clone, Default, PartialEq)]
+$vis struct $Leaf<T> {
+    pub value: T,
+    pub span: $Span,$trivia
+}
+";
+
+const ERROR_VARIANT: &str = "    /// Not terminal recovered from, or a node not parsed yet.
+    #[default]
+    Error,
+";
+
+/// How the tree of a not terminal is built from the nodes of its alternatives.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Shape {
+    /// Not terminal of the grammar.
+    Node,
+    /// `( ... )` group.
+    Group,
+    /// `( ... )?` group, `None` for the empty alternative added by desugaring.
+    Optional,
+    /// `( ... )*` group. Items are pushed after the items of the rest of the list,
+    /// so the list is reversed until the member calling the group reverses it.
+    Star,
+    /// `( ... )+` group, its alternatives end with a call of its `( ... )*` helper.
+    Plus,
+}
+
+/// Node types of the not terminals.
+struct TreeTypes {
+    /// Shape and node type of every not terminal.
+    types: HashMap<String, (Shape, String)>,
+    /// Node types holding a not terminal of the grammar, they are boxed in fields.
+    boxed: HashSet<String>,
+}
+
+/// Enum of a node type being declared.
+struct Declaration {
+    ty: String,
+    not_terminals: Vec<String>,
+    /// Not terminal a group was written in.
+    group_of: String,
+    variants: Vec<String>,
+    /// Variant of every name and fields, alternatives with the same members share it.
+    named: HashMap<(String, String), String>,
+}
+
+/// Return of every not terminal holding its node, `cst` or `ast`, e.g. `E0_cst` in actions.
+pub fn tree_return(tree: ParseTree) -> &'static str {
+    match tree {
+        ParseTree::Ast => "ast",
+        _ => "cst",
+    }
+}
+
+/// Adds the `cst` return to every not terminal and an action building it at the end of every rule,
+/// and appends the types of the tree to the prelude: an enum per not terminal and group with a
+/// variant per alternative and a field per member. Runs after groups are desugared and before
+/// left recursion is eliminated, so the tree follows the alternatives as written.
+///
+/// The `ast` tree, returned as `ast`, follows the annotations of the members: `label=Member` names the
+/// field of the member, `Member!` leaves it out and `Member^` makes the node of the member the node of the
+/// alternative, so the not terminals share the node type named after the not terminal promoted into.
+///
+/// Error recovery and table-driven parsers need default values of returns, their enums
+/// get an `Error` variant, the default and the node of a not terminal recovered from.
+pub fn add_syntax_tree(mut parser_description: ParserDescription, table_driven: bool) -> ParserDescription {
+    let names = Names::new(&parser_description.options);
+    let tree = parser_description.options.tree;
+    let annotated = tree == ParseTree::Ast;
+    let result = tree_return(tree);
+    let tokens: HashMap<String, Vec<Typed>> = parser_description
+        .tokens
+        .iter()
+        .map(|token| (token.name.clone(), token_results(token, &parser_description.options)))
+        .collect();
+    let tree_types = get_types(&parser_description.not_terminal, &names, annotated);
+    let types = &tree_types.types;
+
+    let mut declarations: Vec<Declaration> = Vec::new();
+    for not_term in &mut parser_description.not_terminal {
+        let (shape, ty) = &types[&not_term.name];
+        let position = match declarations.iter().position(|declaration| &declaration.ty == ty) {
+            Some(position) => position,
+            None => {
+                let group_of = not_term.name.split('_').next().unwrap_or_default().to_string();
+                let named = HashMap::new();
+                declarations.push(Declaration { ty: ty.clone(), not_terminals: Vec::new(), group_of, variants: Vec::new(), named });
+                declarations.len() - 1
+            }
+        };
+        let declaration = &mut declarations[position];
+        if *shape == Shape::Node {
+            declaration.not_terminals.push(not_term.name.clone());
+        }
+        let alternatives = match shape {
+            Shape::Optional | Shape::Star => not_term.rules.len() - 1,
+            _ => not_term.rules.len(),
+        };
+        let variants = get_variant_names(&not_term.rules[..alternatives]);
+
+        for (i, rule) in not_term.rules.iter_mut().enumerate() {
+            let action = match variants.get(i) {
+                Some(variant) => {
+                    let mut items = get_items(rule, *shape);
+                    let promoted = items.iter().find(|(_, token)| annotated && token.annotation.promote).copied();
+                    let node = match promoted {
+                        Some((index, token)) if tree_types.is_promoted(&token.name) => {
+                            format!("{}{result}", member_prefix(&token.name, index))
+                        }
+                        _ => {
+                            if let Some(promoted) = promoted {
+                                items = vec![promoted];
+                            } else if annotated {
+                                items.retain(|(_, token)| !token.annotation.drop);
+                            }
+                            let labelled = annotated && items.iter().any(|(_, token)| token.annotation.label.is_some());
+                            let (declared, node) = tree_types.get_variant(&items, labelled, result, &tokens, &names);
+                            let variant = declaration.add_variant(variant, declared);
+                            format!("{ty}::{variant}{node}")
+                        }
+                    };
+                    match shape {
+                        Shape::Node | Shape::Group => format!("{result} = {node};"),
+                        Shape::Optional => format!("{result} = Some({node});"),
+                        Shape::Star | Shape::Plus => {
+                            let (index, list) = get_list_call(rule).expect("list groups call their `*` helper");
+                            format!("{result} = {}{result}; {result}.push({node});", member_prefix(&list.name, index))
+                        }
+                    }
+                }
+                None if *shape == Shape::Optional => format!("{result} = None;"),
+                None => format!("{result} = Vec::new();"),
+            };
+            rule.members.push(RuleMember::Command(action));
+        }
+
+        let return_type = match shape {
+            Shape::Node | Shape::Group => ty.clone(),
+            Shape::Optional => format!("Option<{ty}>"),
+            Shape::Star | Shape::Plus => format!("Vec<{ty}>"),
+        };
+        not_term.returns.push(Typed { name: result.to_string(), ty: return_type });
+    }
+
+    let trivia = match parser_description.options.trivia {
+        Some(_) => "\n    pub trivia: Vec<$Trivia>,",
+        None => "",
+    };
+    let mut code = names.fill(&LEAF.replace("$trivia", trivia));
+    let error = match parser_description.options.recovery.is_some() || table_driven {
+        true => ERROR_VARIANT,
+        false => "",
+    };
+    let derives = match error.is_empty() {
+        true => "Debug, Clone, PartialEq",
+        false => "Debug, Clone, Default, PartialEq",
+    };
+    let kind = match annotated {
+        true => "abstract",
+        false => "concrete",
+    };
+    for Declaration { ty, not_terminals, group_of, variants, .. } in declarations {
+        let description = match &not_terminals[..] {
+            [] => format!("a group of `{group_of}`"),
+            [not_terminal] => format!("`{not_terminal}`"),
+            [rest @ .., last] => format!("`{}` and `{last}`", rest.join("`, `")),
+        };
+        code.push_str(&names.fill(&format!("
+/// Node of {description} in the {kind} syntax tree, a variant per alternative.
+#[derive({derives})]
+$vis enum {ty} {{
+{}{error}}}
+", variants.concat())));
+    }
+
+    parser_description.prelude.push_str(&code);
+    parser_description
+}
+
+impl Declaration {
+    /// Variant of an alternative, numbered when another alternative with other fields has the name.
+    /// The `( ... )+` group and its `( ... )*` helper have the same alternatives and share variants.
+    fn add_variant(&mut self, name: &str, fields: String) -> String {
+        let key = (name.to_string(), fields);
+        if let Some(variant) = self.named.get(&key) {
+            return variant.clone();
+        }
+        let used: HashSet<&String> = self.named.values().collect();
+        let mut variant = name.to_string();
+        let mut number = self.variants.len();
+        while variant == "Error" || used.contains(&variant) {
+            variant = format!("{name}{number}");
+            number += 1;
+        }
+        self.variants.push(format!("    {variant}{},\n", key.1));
+        self.named.insert(key, variant.clone());
+        variant
+    }
+}
+
+impl TreeTypes {
+    /// Fields of a variant and the values of the fields, named when some members are labelled.
+    /// Members without a label are then named after their prefix, e.g. `num0` for `Num0_`.
+    fn get_variant(
+        &self,
+        items: &[(usize, &RuleToken)],
+        labelled: bool,
+        result: &str,
+        tokens: &HashMap<String, Vec<Typed>>,
+        names: &Names,
+    ) -> (String, String) {
+        let fields: Vec<(String, String, String)> = items
+            .iter()
+            .filter_map(|(index, token)| {
+                let (ty, value) = self.get_field(token, *index, result, tokens, names)?;
+                let label = token.annotation.label.clone().unwrap_or_else(|| {
+                    member_prefix(&token.name, *index).trim_end_matches('_').to_lowercase()
+                });
+                Some((label, ty, value))
+            })
+            .collect();
+        if fields.is_empty() {
+            return (String::new(), String::new());
+        }
+        match labelled {
+            true => {
+                let declared = fields.iter().map(|(label, ty, _)| format!("{label}: {ty}")).collect::<Vec<String>>();
+                let values = fields.iter().map(|(label, _, value)| format!("{label}: {value}")).collect::<Vec<String>>();
+                (format!(" {{ {} }}", declared.join(", ")), format!(" {{ {} }}", values.join(", ")))
+            }
+            false => {
+                let declared = fields.iter().map(|(_, ty, _)| ty.as_str()).collect::<Vec<&str>>();
+                let values = fields.iter().map(|(_, _, value)| value.as_str()).collect::<Vec<&str>>();
+                (format!("({})", declared.join(", ")), format!("({})", values.join(", ")))
+            }
+        }
+    }
+
+    /// Whether `Member^` makes the node of the not terminal the node of the alternative,
+    /// the node of a token, an optional or a list is rather the only field of the alternative.
+    fn is_promoted(&self, name: &str) -> bool {
+        matches!(self.types.get(name), Some((Shape::Node | Shape::Group, _)))
+    }
+
+    /// Type of the field of a member and the expression building it from the member results.
+    fn get_field(
+        &self,
+        token: &RuleToken,
+        index: usize,
+        result: &str,
+        tokens: &HashMap<String, Vec<Typed>>,
+        names: &Names,
+    ) -> Option<(String, String)> {
+        let prefix = member_prefix(&token.name, index);
+        if let Some(results) = tokens.get(&token.name) {
+            let value = &results[0];
+            let trivia = match results.len() {
+                1 => String::new(),
+                _ => format!(", trivia: {prefix}trivia.clone()"),
+            };
+            return Some((
+                format!("{}<{}>", names.leaf, value.ty),
+                format!("{} {{ value: {prefix}{}.clone(), span: {prefix}span{trivia} }}", names.leaf, value.name),
+            ));
+        }
+        let (shape, ty) = self.types.get(&token.name)?;
+        let boxed = self.boxed.contains(ty);
+        let field = match shape {
+            Shape::Node | Shape::Group if boxed => (format!("Box<{ty}>"), format!("Box::new({prefix}{result})")),
+            Shape::Node | Shape::Group => (ty.clone(), format!("{prefix}{result}")),
+            Shape::Optional if boxed => (format!("Option<Box<{ty}>>"), format!("{prefix}{result}.map(Box::new)")),
+            Shape::Optional => (format!("Option<{ty}>"), format!("{prefix}{result}")),
+            Shape::Star | Shape::Plus => (
+                format!("Vec<{ty}>"),
+                format!("{{ let mut items = {prefix}{result}; items.reverse(); items }}"),
+            ),
+        };
+        Some(field)
+    }
+}
+
+/// Shape and node type of every not terminal. Groups are named after the not terminal they were
+/// written in and their number, e.g. `EGroup1Node` for the `E_1_star` helper. With annotations,
+/// not terminals promoted into each other share the type of the first one declared promoted into.
+fn get_types(not_terminals: &[NotTerminal], names: &Names, annotated: bool) -> TreeTypes {
+    let mut types: HashMap<String, (Shape, String)> = not_terminals
+        .iter()
+        .map(|not_term| {
+            let parts: Vec<&str> = not_term.name.rsplitn(3, '_').collect();
+            let entry = match parts[..] {
+                [kind, number, root] => {
+                    let shape = match kind {
+                        "opt" => Shape::Optional,
+                        "star" => Shape::Star,
+                        "plus" => Shape::Plus,
+                        _ => Shape::Group,
+                    };
+                    (shape, names.node(&format!("{root}Group{number}")))
+                }
+                _ => (Shape::Node, names.node(&not_term.name)),
+            };
+            (not_term.name.clone(), entry)
+        })
+        .collect();
+
+    // The `*` helper of a `( ... )+` group has the items of the group.
+    for not_term in not_terminals {
+        let (Shape::Plus, ty) = &types[&not_term.name] else {
+            continue;
+        };
+        let ty = ty.clone();
+        if let Some((_, star)) = not_term.rules.first().and_then(get_list_call) {
+            if let Some(entry) = types.get_mut(&star.name) {
+                entry.1 = ty;
+            }
+        }
+    }
+
+    if annotated {
+        merge_promoted(not_terminals, &mut types);
+    }
+    let boxed = not_terminals
+        .iter()
+        .filter_map(|not_term| match &types[&not_term.name] {
+            (Shape::Node, ty) => Some(ty.clone()),
+            _ => None,
+        })
+        .collect();
+    TreeTypes { types, boxed }
+}
+
+/// Gives not terminals connected by `Member^` one node type.
+fn merge_promoted(not_terminals: &[NotTerminal], types: &mut HashMap<String, (Shape, String)>) {
+    let mut classes: HashMap<String, String> = HashMap::new();
+
+    let mut targets: Vec<&str> = Vec::new();
+    for not_term in not_terminals {
+        let (shape, ty) = &types[&not_term.name];
+        for rule in &not_term.rules {
+            let promoted = get_items(rule, *shape).into_iter().find(|(_, token)| token.annotation.promote);
+            let Some((_, token)) = promoted else {
+                continue;
+            };
+            let Some((Shape::Node | Shape::Group, target)) = types.get(&token.name) else {
+                continue;
+            };
+            targets.push(&token.name);
+            let (from, to) = (class_of(&classes, ty), class_of(&classes, target));
+            if from != to {
+                classes.insert(from, to);
+            }
+        }
+    }
+
+    // Classes are named after the first not terminal declared which is promoted into,
+    // or the first one of the grammar.
+    let mut representatives: HashMap<String, String> = HashMap::new();
+    let promoted_first = not_terminals.iter().filter(|not_term| targets.contains(&not_term.name.as_str()));
+    let rest = not_terminals.iter().filter(|not_term| types[&not_term.name].0 == Shape::Node);
+    for not_term in promoted_first.chain(rest).chain(not_terminals) {
+        let ty = &types[&not_term.name].1;
+        representatives.entry(class_of(&classes, ty)).or_insert(ty.clone());
+    }
+    for (_, ty) in types.values_mut() {
+        *ty = representatives[&class_of(&classes, ty)].clone();
+    }
+}
+
+/// Type standing for the merged types of `ty`.
+fn class_of(classes: &HashMap<String, String>, ty: &str) -> String {
+    let mut ty = ty.to_string();
+    while let Some(parent) = classes.get(&ty) {
+        ty = parent.clone();
+    }
+    ty
+}
+
+/// Variants named after the not terminals and tokens of the alternatives, `Empty` for an empty one.
+fn get_variant_names(rules: &[Rule]) -> Vec<String> {
+    rules
+        .iter()
+        .map(|rule| {
+            let name: String = rule
+                .members
+                .iter()
+                .filter_map(|member| match member {
+                    RuleMember::RuleCall(RuleToken { name, .. }) if !name.contains('_') => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect();
+            match name.is_empty() {
+                true => "Empty".to_string(),
+                false => name,
+            }
+        })
+        .collect()
+}
+
+/// Call members making up the node with their index, without the call continuing a list.
+fn get_items(rule: &Rule, shape: Shape) -> Vec<(usize, &RuleToken)> {
+    let list_call = match shape {
+        Shape::Star | Shape::Plus => get_list_call(rule).map(|(index, _)| index),
+        _ => None,
+    };
+    rule.members
+        .iter()
+        .enumerate()
+        .filter_map(|(i, member)| match member {
+            RuleMember::RuleCall(token) if Some(i) != list_call => Some((i, token)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Last call of the rule, in `( ... )*` and `( ... )+` helpers the call of the `*` helper.
+fn get_list_call(rule: &Rule) -> Option<(usize, &RuleToken)> {
+    rule.members.iter().enumerate().rev().find_map(|(i, member)| match member {
+        RuleMember::RuleCall(token) => Some((i, token)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_generator::ebnf::desugar_groups;
+    use crate::parser_generator::parser_description::{parse_parser_description, ParserDescription, RuleMember};
+    use super::add_syntax_tree;
+
+    fn with_tree(not_terminals: &str) -> ParserDescription {
+        with_options("tree = \"cst\";", not_terminals)
+    }
+
+    fn with_options(options: &str, not_terminals: &str) -> ParserDescription {
+        let grammar = format!(
+            "Options {{ {options} }} Prelude {{}} Skip {{}} Tokens {{ Num -> \"[0-9]+\" : i64 = |s| s.parse(); Plus -> \"\\\\+\"; Comma -> \",\"; }} NotTerminals {{ {not_terminals} }}"
+        );
+        add_syntax_tree(desugar_groups(parse_parser_description(&grammar).unwrap()), false)
+    }
+
+    fn action(description: &ParserDescription, not_terminal: usize, rule: usize) -> String {
+        match description.not_terminal[not_terminal].rules[rule].members.last() {
+            Some(RuleMember::Command(code)) => code.clone(),
+            _ => panic!("rule doesn't end with an action"),
+        }
+    }
+
+    #[test]
+    fn enum_per_not_terminal() {
+        let description = with_tree("S {} {res # i64} -> E {res = 1;}; E {} {} -> E Plus Num | Num | ;");
+        assert!(description.prelude.contains("pub enum SNode {\n    E(Box<ENode>),\n"));
+        assert!(description.prelude.contains("pub enum ENode {\n    EPlusNum(Box<ENode>, Leaf<String>, Leaf<i64>),\n    Num(Leaf<i64>),\n    Empty,\n"));
+        assert!(description.prelude.contains("pub struct Leaf<T> {\n    pub value: T,\n    pub span: Span,\n}"));
+        assert_eq!(vec!["res", "cst"], description.not_terminal[0].returns.iter().map(|t| t.name.as_str()).collect::<Vec<_>>());
+        assert_eq!("cst = SNode::E(Box::new(E0_cst));", action(&description, 0, 0));
+        assert_eq!(
+            "cst = ENode::EPlusNum(Box::new(E0_cst), Leaf { value: Plus1_ident.clone(), span: Plus1_span }, Leaf { value: Num2_value.clone(), span: Num2_span });",
+            action(&description, 1, 0),
+        );
+        assert_eq!("cst = ENode::Empty;", action(&description, 1, 2));
+    }
+
+    #[test]
+    fn groups_are_lists_and_options() {
+        let description = with_tree("S {} {} -> Num (Comma Num)* (Plus)?; E {} {} -> (Num | Plus)+;");
+        assert!(description.prelude.contains("pub enum SNode {\n    Num(Leaf<i64>, Vec<SGroup1Node>, Option<SGroup2Node>),\n"));
+        assert!(description.prelude.contains("pub enum EGroup1Node {\n    Num(Leaf<i64>),\n    Plus(Leaf<String>),\n"));
+        assert_eq!(
+            "cst = SNode::Num(Leaf { value: Num0_value.clone(), span: Num0_span }, { let mut items = S_1_star1_cst; items.reverse(); items }, S_2_opt2_cst);",
+            action(&description, 0, 0),
+        );
+
+        let names: Vec<&str> = description.not_terminal.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(vec!["S", "E", "S_1_star", "S_2_opt", "E_2_star", "E_1_plus"], names);
+        assert_eq!("cst = S_1_star2_cst; cst.push(SGroup1Node::CommaNum(Leaf { value: Comma0_ident.clone(), span: Comma0_span }, Leaf { value: Num1_value.clone(), span: Num1_span }));", action(&description, 2, 0));
+        assert_eq!("cst = Vec::new();", action(&description, 2, 1));
+        assert_eq!("cst = None;", action(&description, 3, 1));
+        assert_eq!("cst = E_2_star1_cst; cst.push(EGroup1Node::Plus(Leaf { value: Plus0_ident.clone(), span: Plus0_span }));", action(&description, 5, 1));
+        assert_eq!(1, description.prelude.matches("pub enum EGroup1Node").count());
+    }
+
+    #[test]
+    fn error_variant_for_default_values() {
+        assert!(!with_tree("S {} {} -> Num;").prelude.contains("Error"));
+        let grammar = "Options { recovery = \"\"; tree = \"cst\"; } Prelude {} Skip {} Tokens { Num -> \"[0-9]+\"; } NotTerminals { S {} {} -> Num; }";
+        let description = add_syntax_tree(parse_parser_description(grammar).unwrap(), false);
+        assert!(description.prelude.contains("#[derive(Debug, Clone, Default, PartialEq)]\npub enum SNode {\n    Num(Leaf<String>),\n    /// Not terminal recovered from, or a node not parsed yet.\n    #[default]\n    Error,\n}"));
+        assert!(with_tree("S {} {} -> Num;").prelude.contains("#[derive(Debug, Clone, PartialEq)]\npub enum SNode"));
+    }
+
+    #[test]
+    fn annotations_shape_the_abstract_tree() {
+        let description = with_options(
+            "tree = \"ast\";",
+            "S {} {} -> E^; E {} {} -> lhs=E Plus! rhs=T | T^; T {} {} -> Num | Comma! E^ Comma!;",
+        );
+        let prelude = &description.prelude;
+        assert!(prelude.contains("/// Node of `S`, `E` and `T` in the abstract syntax tree, a variant per alternative.\n"));
+        assert!(prelude.contains("pub enum ENode {\n    EPlusT { lhs: Box<ENode>, rhs: Box<ENode> },\n    Num(Leaf<i64>),\n}"));
+        assert!(!prelude.contains("SNode") && !prelude.contains("TNode"));
+        assert_eq!("ENode", description.not_terminal[0].returns[0].ty);
+        assert_eq!("ast = E0_ast;", action(&description, 0, 0));
+        assert_eq!("ast = ENode::EPlusT { lhs: Box::new(E0_ast), rhs: Box::new(T2_ast) };", action(&description, 1, 0));
+        assert_eq!("ast = E1_ast;", action(&description, 2, 1));
+    }
+
+    #[test]
+    fn promoted_tokens_and_lists_are_the_only_field() {
+        let description = with_options(
+            "tree = \"ast\";",
+            "S {} {} -> Plus Num^ | items=(Num Comma!)* Plus num=Num;",
+        );
+        assert!(description.prelude.contains("pub enum SNode {\n    PlusNum(Leaf<i64>),\n    PlusNum1 { items: Vec<SGroup1Node>, plus1: Leaf<String>, num: Leaf<i64> },\n}"));
+        assert!(description.prelude.contains("pub enum SGroup1Node {\n    NumComma(Leaf<i64>),\n}"));
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::{HashMap, HashSet};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::parser_generator::action_code::{identifiers, member_prefix, member_results, overwrites_first};
+use crate::parser_generator::first_follow::{PredictionRow, PredictionTable};
+use crate::parser_generator::ll1_conflict::ConflictReport;
+use crate::parser_generator::names::Names;
+use crate::parser_generator::parser_description::{NotTerminal, ParserDescription, Rule, RuleMember, RuleToken, Typed};
+use crate::parser_generator::parser_generator::{
+    check_ll1, generate_parse_func, generate_token_functions, get_first_follow, get_input_args_with_self,
+    get_prediction_table, get_return_type, get_sync_match, get_tuple, PARSER, PARSER_IMPL, RECOVER,
+};
+
+const S: &str = "S";
+
+lazy_static! {
+    static ref TYPED_LET_REGEX: Regex = Regex::new(r"let\s+(?:mut\s+)?([A-Za-z_][A-Za-z0-9_]*)\s*:\s*([^=;]+)").unwrap();
+}
+
+const STEP_TYPES: &str = "
+/// Step of a not terminal, it either calls another not terminal or returns.
+enum $Step<N> {
+    Call($Frame),
+    Return($Returned<N>),
+}
+";
+
+/// Table-driven parser: the rule of a not terminal is looked up in `TABLE` and the not
+/// terminals are run by a loop over a stack of frames, so nesting depth isn't limited by
+/// the call stack. Frames keep arguments, returns and member results of a not terminal
+/// between its steps, their types must implement `Default`.
+pub fn generate_table_parser(parser_description: &ParserDescription) -> Result<String, ConflictReport> {
+    check_ll1(parser_description)?;
+    let names = Names::new(&parser_description.options);
+    let table = get_prediction_table(parser_description);
+    let results = member_results(parser_description);
+
+    let mut answer = String::new();
+    answer.push_str(&names.fill(PARSER));
+    answer.push_str(&get_table(&table));
+    answer.push_str(&get_frames(parser_description, &results, &names));
+    answer.push_str(&names.fill(STEP_TYPES));
+
+    answer.push_str(&names.fill(PARSER_IMPL));
+    answer.push_str(&generate_token_functions(parser_description, &names));
+    answer.push_str(&get_driver(parser_description, &names));
+    for (index, (not_term, row)) in parser_description.not_terminal.iter().zip(&table.rows).enumerate() {
+        answer.push_str(&get_step_function(not_term, index, row, &table.tokens, parser_description, &results, &names));
+    }
+    if let Some(sync) = &parser_description.options.recovery {
+        answer.push_str(&get_frame_recovery(parser_description, sync, &names));
+    }
+    answer.push_str("}\n");
+    if parser_description.options.recovery.is_some() {
+        answer.push_str(&names.fill(RECOVER));
+    }
+
+    answer.push_str(&generate_parse_func(parser_description, &names));
+    Ok(answer)
+}
+
+fn get_table(table: &PredictionTable) -> String {
+    let rows = table
+        .rows
+        .iter()
+        .map(|row| {
+            let cells = row
+                .cells
+                .iter()
+                .map(|cell| cell.first().map_or("NO_RULE".to_string(), |rule| rule.to_string()))
+                .collect::<Vec<String>>()
+                .join(", ");
+            format!("    [{cells}],\n")
+        })
+        .collect::<String>();
+    format!("
+const NO_RULE: u16 = u16::MAX;
+
+/// Rule of every not terminal, in declaration order, for every token kind.
+const TABLE: [[u16; {}]; {}] = [
+{rows}];
+", table.tokens.len(), table.rows.len())
+}
+
+/// Frame struct of every not terminal, `Frame` and `Returned` with a variant for every not terminal.
+fn get_frames(parser_description: &ParserDescription, results: &HashMap<String, Vec<Typed>>, names: &Names) -> String {
+    let mut answer = String::new();
+    let mut frames = String::new();
+    let mut returned = String::new();
+    for not_term in &parser_description.not_terminal {
+        let name = &not_term.name;
+        let frame = names.not_terminal_frame(name);
+        let fields = get_frame_variables(not_term, results)
+            .iter()
+            .map(|Typed { name, ty }| format!("    {name}: {ty},\n"))
+            .collect::<String>();
+        answer.push_str(&format!("
+#[derive(Default)]
+struct {frame} {{
+    _rule: usize,
+    _segment: usize,
+    _id: usize,
+    _span_start: usize,
+{fields}}}
+"));
+        frames.push_str(&format!("    {name}({frame}),\n"));
+        let return_types = not_term.returns.iter().map(|t| t.ty.as_str()).collect::<Vec<&str>>().join(",");
+        returned.push_str(&format!("    {name}(N, {}, ({return_types})),\n", names.span));
+    }
+    answer.push_str(&format!("
+/// Not terminal waiting on the stack for its next step, its children are kept next to it.
+enum {} {{
+{frames}}}
+
+/// Node, span and returns of a finished not terminal.
+enum {}<N> {{
+{returned}}}
+", names.frame, names.returned));
+    answer
+}
+
+/// Arguments, returns, the results of all the members of a not terminal and
+/// the locals its action code declares with a type.
+fn get_frame_variables(not_term: &NotTerminal, results: &HashMap<String, Vec<Typed>>) -> Vec<Typed> {
+    let mut variables: Vec<Typed> = Vec::new();
+    let mut add = |variable: Typed| {
+        if !variables.iter().any(|v| v.name == variable.name) {
+            variables.push(variable);
+        }
+    };
+    not_term.args.iter().chain(&not_term.returns).cloned().for_each(&mut add);
+    for rule in &not_term.rules {
+        for (i, member) in rule.members.iter().enumerate() {
+            match member {
+                RuleMember::RuleCall(RuleToken { name, .. }) => {
+                    for Typed { name: result, ty } in &results[name] {
+                        add(Typed { name: format!("{}{result}", member_prefix(name, i)), ty: ty.clone() });
+                    }
+                }
+                RuleMember::Command(code) => get_typed_locals(code).for_each(&mut add),
+                RuleMember::Group(_) => unreachable!("groups are desugared before parser generation"),
+            }
+        }
+    }
+    variables
+}
+
+/// Locals declared with a type by the code, like `let mut sum: i64;`.
+fn get_typed_locals(code: &str) -> impl Iterator<Item = Typed> + '_ {
+    TYPED_LET_REGEX
+        .captures_iter(code)
+        .map(|caps| Typed { name: caps[1].to_string(), ty: caps[2].trim().to_string() })
+}
+
+/// Function of the start not terminal running the frames until the start frame returns.
+fn get_driver(parser_description: &ParserDescription, names: &Names) -> String {
+    let start = parser_description.not_terminal.iter().find(|s| s.name == S).unwrap();
+    let input_types = get_input_args_with_self(&start.args);
+    let return_type = get_return_type(&start.returns, names);
+    let args = start.args.iter().map(|arg| format!("{}, ", arg.name)).collect::<String>();
+    let Names { frame, step, returned, .. } = names;
+    let start_frame = names.not_terminal_frame(S);
+    let steps = parser_description
+        .not_terminal
+        .iter()
+        .map(|not_term| format!("{frame}::{0}(frame) => self.step_{0}(frame, children, returned.take()),\n", not_term.name))
+        .collect::<String>();
+    let error = match parser_description.options.recovery {
+        Some(_) => "Err(error) => {
+                let (frame, _) = stack.pop().unwrap();
+                returned = Some(self.recover_frame(frame, error));
+            }",
+        None => "Err(error) => return Err(error),",
+    };
+    format!("
+    fn {S}{input_types} -> {return_type} {{
+        let start = {start_frame} {{ {args}_id: self.counter, _span_start: self.pointer, ..{start_frame}::default() }};
+        self.counter += 1;
+        let mut stack = vec![({frame}::{S}(start), Vec::new())];
+        let mut returned: Option<{returned}<B::Node>> = None;
+        while let Some((frame, children)) = stack.last_mut() {{
+            let step = match frame {{
+                {steps}
+            }};
+            match step {{
+                Ok({step}::Call(frame)) => stack.push((frame, Vec::new())),
+                Ok({step}::Return(result)) => {{
+                    stack.pop();
+                    returned = Some(result);
+                }}
+                {error}
+            }}
+        }}
+        match returned {{
+            Some({returned}::{S}(tree, span, res)) => Ok((tree, span, res)),
+            _ => unreachable!(\"the start frame returns last\"),
+        }}
+    }}
+")
+}
+
+/// Part of a rule run in one step, up to and including a call of a not terminal.
+struct Segment<'a> {
+    /// Index of the first member in the rule.
+    start: usize,
+    members: &'a [RuleMember],
+    /// Not terminal called at the end of the segment.
+    call: Option<&'a RuleToken>,
+}
+
+fn get_segments<'a>(rule: &'a Rule, not_terminals: &HashSet<&str>) -> Vec<Segment<'a>> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for (i, member) in rule.members.iter().enumerate() {
+        if let RuleMember::RuleCall(token) = member {
+            if not_terminals.contains(token.name.as_str()) {
+                segments.push(Segment { start, members: &rule.members[start..i], call: Some(token) });
+                start = i + 1;
+            }
+        }
+    }
+    segments.push(Segment { start, members: &rule.members[start..], call: None });
+    segments
+}
+
+impl<'a> Segment<'a> {
+    /// Commands and call arguments in the order they run.
+    fn code(&self) -> Vec<&'a str> {
+        let mut code: Vec<&str> = self
+            .members
+            .iter()
+            .map(|member| match member {
+                RuleMember::RuleCall(RuleToken { args, .. }) => args.as_str(),
+                RuleMember::Command(command) => command.as_str(),
+                RuleMember::Group(_) => unreachable!("groups are desugared before parser generation"),
+            })
+            .collect();
+        code.extend(self.call.map(|call| call.args.as_str()));
+        code
+    }
+}
+
+/// Whether the value of the variable is read after the segment, by the next segments or as a return.
+fn is_read_after(variable: &str, later: &[Segment], returns: &HashSet<&str>) -> bool {
+    for code in later.iter().flat_map(Segment::code) {
+        if let Some(overwrites) = overwrites_first(code, variable) {
+            return !overwrites;
+        }
+    }
+    returns.contains(variable)
+}
+
+/// `step_{name}` choosing the rule by the table and running the segment of the rule the frame is at.
+///
+/// The variables a segment uses are taken out of the frame, so the action code works on owned values
+/// like in the recursive parser. Before a call the ones read after it are put back, they must not
+/// have been moved out by then.
+fn get_step_function(
+    not_term: &NotTerminal,
+    index: usize,
+    row: &PredictionRow,
+    tokens: &[String],
+    parser_description: &ParserDescription,
+    results: &HashMap<String, Vec<Typed>>,
+    names: &Names,
+) -> String {
+    let name = &not_term.name;
+    let not_terminals: HashSet<&str> = parser_description.not_terminal.iter().map(|t| t.name.as_str()).collect();
+    let variables: HashSet<String> = get_frame_variables(not_term, results).into_iter().map(|v| v.name).collect();
+    let returns: HashSet<&str> = not_term.returns.iter().map(|r| r.name.as_str()).collect();
+
+    let expected = row
+        .cells
+        .iter()
+        .zip(tokens)
+        .filter(|(cell, _)| !cell.is_empty())
+        .map(|(_, token)| format!("\"{token}\""))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let mut cases = String::new();
+    for (rule_index, rule) in not_term.rules.iter().enumerate() {
+        if !row.cells.iter().any(|cell| cell.first() == Some(&rule_index)) {
+            continue;
+        }
+        let segments = get_segments(rule, &not_terminals);
+        let used: Vec<HashSet<&str>> = segments
+            .iter()
+            .map(|segment| segment.code().into_iter().flat_map(identifiers).collect())
+            .collect();
+        for (k, segment) in segments.iter().enumerate() {
+            let mut bound: HashSet<String> = HashSet::new();
+            let mut body = String::new();
+            if k > 0 {
+                let previous = &segments[k - 1];
+                let call = previous.call.unwrap();
+                let prefix = member_prefix(&call.name, previous.start + previous.members.len());
+                let tuple = get_tuple(&prefix, &results[&call.name][..results[&call.name].len() - 1]);
+                body.push_str(&format!(
+                    "let Some({}::{}(child, {prefix}span, {tuple})) = returned else {{ unreachable!(\"a call returns before the next step\") }};
+                    children.push(child);\n",
+                    names.returned,
+                    call.name,
+                ));
+                bound.extend(results[&call.name].iter().map(|r| format!("{prefix}{}", r.name)));
+            }
+            for (i, member) in segment.members.iter().enumerate() {
+                match member {
+                    RuleMember::RuleCall(RuleToken { name, args, .. }) => {
+                        let prefix = member_prefix(name, segment.start + i);
+                        let tuple = get_tuple(&prefix, &results[name][..results[name].len() - 1]);
+                        body.push_str(&format!("let (child, {prefix}span, {tuple}) = self.{name}{args}?;\nchildren.push(child);\n"));
+                        bound.extend(results[name].iter().map(|r| format!("{prefix}{}", r.name)));
+                    }
+                    RuleMember::Command(command) => {
+                        body.push_str(command);
+                        body.push_str(if command.trim_end().ends_with(';') { "\n" } else { ";\n" });
+                        bound.extend(get_typed_locals(command).map(|local| local.name));
+                    }
+                    RuleMember::Group(_) => unreachable!("groups are desugared before parser generation"),
+                }
+            }
+
+            let mut loaded: Vec<&str> = used[k].iter().copied().filter(|v| variables.contains(*v)).collect();
+            if segment.call.is_none() {
+                loaded.extend(returns.iter().copied().filter(|r| !used[k].contains(r)));
+            }
+            loaded.retain(|v| !bound.contains(*v));
+            loaded.sort();
+            let mut loads = loaded
+                .iter()
+                .map(|v| format!("let mut {v} = std::mem::take(&mut frame.{v});\n"))
+                .collect::<String>();
+            if used[k].contains("span_start") {
+                loads.push_str("let span_start = frame._span_start;\n");
+            }
+
+            let end = match segment.call {
+                Some(RuleToken { name: callee, args, .. }) => {
+                    let mut saved: Vec<&str> = loaded
+                        .iter()
+                        .copied()
+                        .chain(bound.iter().map(String::as_str))
+                        .filter(|v| is_read_after(v, &segments[k + 1..], &returns))
+                        .collect();
+                    saved.sort();
+                    let saves = saved.iter().map(|v| format!("frame.{v} = {v};\n")).collect::<String>();
+                    let callee_args = &parser_description.not_terminal.iter().find(|t| &t.name == callee).unwrap().args;
+                    let fields = callee_args.iter().map(|arg| format!("{}, ", arg.name)).collect::<String>();
+                    let callee_frame = names.not_terminal_frame(callee);
+                    format!("let child = {{
+                        let {} = {args};
+                        {callee_frame} {{ {fields}_id: self.counter, _span_start: self.pointer, ..{callee_frame}::default() }}
+                    }};
+                    self.counter += 1;
+                    {saves}frame._segment = {};
+                    Ok({}::Call({}::{callee}(child)))\n",
+                        get_tuple("", callee_args),
+                        k + 1,
+                        names.step,
+                        names.frame,
+                    )
+                }
+                None => format!(
+                    "let node_span = self.span_since(frame._span_start);
+                    let node = self.builder.node(frame._id, \"{name}\", node_span, std::mem::take(children));
+                    Ok({}::Return({}::{name}(node, node_span, {})))\n",
+                    names.step,
+                    names.returned,
+                    get_tuple("", &not_term.returns),
+                ),
+            };
+            cases.push_str(&format!("({rule_index}, {k}) => {{\n{loads}{body}{end}}}\n"));
+        }
+    }
+
+    let input_types = format!(
+        "(&mut self, frame: &mut {}, children: &mut Vec<B::Node>, returned: Option<{}<B::Node>>)",
+        names.not_terminal_frame(name),
+        names.returned,
+    );
+    format!("
+    fn step_{name}{input_types} -> Result<{}<B::Node>, {}> {{
+        if frame._segment == 0 {{
+            frame._rule = match TABLE[{index}][self.tokens[self.pointer].kind()] {{
+                NO_RULE => return Err(self.expected(&[{expected}])),
+                rule => rule as usize,
+            }};
+        }}
+        match (frame._rule, frame._segment) {{
+            {cases}
+            _ => unreachable!(\"no such rule and segment\"),
+        }}
+    }}
+", names.step, names.parse_error)
+}
+
+/// `recover_frame` recovering from an error in the not terminal of the frame at its FOLLOW set
+/// or at a `sync` token, the node of the not terminal is then an `error` leaf and its returns have default values.
+fn get_frame_recovery(parser_description: &ParserDescription, sync: &[String], names: &Names) -> String {
+    let first_follow = get_first_follow(parser_description);
+    let mut cases = String::new();
+    for (not_term, sets) in parser_description.not_terminal.iter().zip(&first_follow.sets) {
+        let follow: HashSet<&str> = sets.follow.iter().map(String::as_str).collect();
+        let sync_match = get_sync_match(&follow, sync, names);
+        let defaults = format!("({})", not_term.returns.iter().map(|_| "Default::default()").collect::<Vec<&str>>().join(","));
+        cases.push_str(&format!("$Frame::{name}(frame) => {{
+                self.recover(error, |token| {sync_match});
+                let id = self.counter;
+                self.counter += 1;
+                let span = self.span_since(frame._span_start);
+                $Returned::{name}(self.builder.leaf(id, \"error\", span), span, {defaults})
+            }}
+", name = not_term.name));
+    }
+    names.fill(&format!("
+    fn recover_frame(&mut self, frame: $Frame, error: $ParseError) -> $Returned<B::Node> {{
+        match frame {{
+            {cases}
+        }}
+    }}
+"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_generator::parser_description::parse_parser_description;
+    use super::generate_table_parser;
+
+    const LIST: &str = r#"
+Prelude {}
+Skip { " "; }
+Tokens {
+    Num -> "[0-9]+";
+    Plus -> "\\+";
+}
+NotTerminals {
+    S {} {items # Vec<String>} -> {items = Vec::new();} L(items) {items = L1_items;};
+    L {items # Vec<String>} {items # Vec<String>} -> Num {items.push(Num0_ident);} L(items) {items = L2_items;} | ;
+}
+"#;
+
+    #[test]
+    fn table_rows_follow_declaration_order() {
+        let description = parse_parser_description(LIST).unwrap();
+        let code = generate_table_parser(&description).unwrap();
+        assert!(code.contains("const TABLE: [[u16; 3]; 2] = [\n    [0, NO_RULE, 0],\n    [0, NO_RULE, 1],\n];"));
+        assert!(code.contains("NO_RULE => return Err(self.expected(&[\"Num\", \"Eof\"])),"));
+    }
+
+    #[test]
+    fn moved_values_overwritten_after_a_call_stay_out_of_the_frame() {
+        let description = parse_parser_description(LIST).unwrap();
+        let code = generate_table_parser(&description).unwrap();
+        assert!(code.contains("let mut items = std::mem::take(&mut frame.items);"));
+        assert!(!code.contains("frame.items = items;"));
+        assert!(code.contains("Ok(Step::Call(Frame::L(child)))"));
+    }
+
+    #[test]
+    fn action_code_is_not_filled() {
+        let grammar = format!("Options {{ prefix = \"Calc\"; }}\n{}", LIST.replace("Num0_ident", "format!(\"$vis $Token {}\", Num0_ident)"));
+        let description = parse_parser_description(&grammar).unwrap();
+        let code = generate_table_parser(&description).unwrap();
+        assert!(code.contains("items.push(format!(\"$vis $Token {}\", Num0_ident));\n"));
+        assert!(code.contains("-> Result<CalcStep<B::Node>, CalcParseError>"));
+        assert!(code.contains("Ok(CalcStep::Call(CalcFrame::L(child)))"));
+        assert!(code.contains("struct CalcLFrame {"));
+        assert!(!code.contains(";;"));
+    }
+}
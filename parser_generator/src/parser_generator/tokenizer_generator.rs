@@ -1,178 +1,511 @@
-use crate::parser_generator::parser_description::{ParserDescription, Token};
+use crate::parser_generator::names::Names;
+use crate::parser_generator::parser_description::{
+    ModeChange, ParserDescription, Token, TokenMode, TriviaAttachment, DEFAULT_MODE,
+};
+
+pub const EOF_TOKEN: &str = "Eof";
+
+const SPAN: &str =
+    "/// Location of a token or of a not terminal in the input.
+/// `start` and `end` are byte offsets, `line` and `column` of the start count from 1.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+$vis struct $Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl $Span {
+    /// Span from the start of `self` to the end of `end`.
+    pub fn to(self, end: $Span) -> $Span {
+        $Span { end: end.end, ..self }
+    }
+}";
+
+const TRIVIA: &str =
+    "/// Skipped text attached to a token.
+#[derive(Debug, Clone, Eq, PartialEq)]
+$vis struct $Trivia {
+    pub text: String,
+    pub span: $Span,
+}";
+
+const TRIVIA_IMPL: &str =
+    "impl $Tokenizer {
+    /// Input of the tokens restored from their text and trivia.
+    pub fn source_text(tokens: &[$Token]) -> String {
+        let mut source = String::new();
+        for token in tokens {
+            let span = token.span();
+            for trivia in token.trivia().iter().filter(|trivia| trivia.span.end <= span.start) {
+                source.push_str(&trivia.text);
+            }
+            source.push_str(token.text());
+            for trivia in token.trivia().iter().filter(|trivia| trivia.span.start >= span.end && span.start < span.end) {
+                source.push_str(&trivia.text);
+            }
+        }
+        source
+    }
+}";
 
-pub const EOF_TOKEN: &'static str = "Eof";
+const CONVERT: &str =
+    "
+    /// Converts the text of a token to its value, a failed conversion is an error at the token.
+    fn convert<T, E: std::fmt::Display>(
+        conversion: impl Fn(&str) -> Result<T, E>,
+        token: &str,
+        text: &str,
+        span: $Span,
+    ) -> Result<T, $ParseError> {
+        conversion(text).map_err(|err| $ParseError { span, message: format!(\"Invalid {token} `{text}`: {err}\") })
+    }
+";
 
-const PARSE_ERROR: &'static str =
+const PARSE_ERROR: &str =
     "#[derive(Debug)]
-pub struct ParseError {
-    pub position: usize,
+$vis struct $ParseError {
+    pub span: $Span,
     pub message: String,
+}
+
+impl std::fmt::Display for $ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, \"{}:{}: {}\", self.span.line, self.span.column, self.message)
+    }
 }";
 
-const TOKENIZER_STRUCT: &'static str =
-    "pub struct Tokenizer {
+const TOKENIZER_STRUCT: &str =
+    "$vis struct $Tokenizer {
      input: String,
      pointer: usize,
+     line: usize,
+     column: usize,
+     /// Stack of the modes in `MODES`, the last one is matched.
+     modes: Vec<usize>,
 }";
 
-const TOKENIZER_BASE_IMPL: &'static str =
+const LEXER_MODE: &str =
+    "#[derive(Clone, Copy)]
+enum $ModeChange {
+    Push(usize),
+    Pop,
+    Switch(usize),
+}
+
+/// Patterns of one tokenizer mode, its tokens followed by its skip patterns, in one
+/// multi-pattern regex which finds all patterns matching at the pointer in one pass.
+/// Only the matched patterns are run again to find where their longest matches end,
+/// so `a|ab` matches all of `ab`.
+struct $LexerMode {
+    lexer: meta::Regex,
+    patterns: Vec<meta::Regex>,
+    /// Token made by the pattern, `None` for skip patterns.
+    tokens: Vec<Option<usize>>,
+    priorities: Vec<i32>,
+    changes: Vec<Option<$ModeChange>>,
+}
+
+impl $LexerMode {
+    fn new(sources: &[&str], tokens: Vec<Option<usize>>, priorities: Vec<i32>, changes: Vec<Option<$ModeChange>>) -> $LexerMode {
+        $LexerMode {
+            lexer: meta::Builder::new()
+                .configure(meta::Config::new().match_kind(MatchKind::All))
+                .build_many(sources)
+                .unwrap(),
+            patterns: sources
+                .iter()
+                .map(|p| meta::Builder::new().configure(meta::Config::new().match_kind(MatchKind::All)).build(p).unwrap())
+                .collect(),
+            tokens,
+            priorities,
+            changes,
+        }
+    }
+}";
+
+const TOKENIZER_BASE_IMPL: &str =
     "
-impl Tokenizer {
-        fn new(input: String) -> Tokenizer {
-            Tokenizer{
+impl $Tokenizer {
+        fn new(input: String) -> $Tokenizer {
+            $Tokenizer{
                 input,
                 pointer: 0,
+                line: 1,
+                column: 1,
+                modes: vec![0],
             }
         }
 
-        pub fn tokenize(input: String) -> Result<Vec<Token>, ParseError> {
-            Tokenizer::new(input).run_tokenizing()
+        pub fn tokenize(input: String) -> Result<Vec<$Token>, $ParseError> {
+            $Tokenizer::new(input).run_tokenizing()
         }
 
-        fn skip(&mut self) -> bool {
-            for regex in SKIP_REGEX.iter() {
-                if let Some(m) = regex.find_at(&self.input, self.pointer) {
-                     let s = m.start();
-                     if s == self.pointer {
-                         self.pointer = m.end();
-                         return true;
-                     }
+        /// Moves the pointer to `end` and returns the span of the passed input.
+        fn advance(&mut self, end: usize) -> $Span {
+            let span = $Span { start: self.pointer, end, line: self.line, column: self.column };
+            for c in self.input[self.pointer..end].chars() {
+                if c == '\\n' {
+                    self.line += 1;
+                    self.column = 1;
+                } else {
+                    self.column += 1;
                 }
             }
-            false
+            self.pointer = end;
+            span
         }
 
-        fn run_tokenizing(&mut self) -> Result<Vec<Token>, ParseError> {
-            let mut tokens: Vec<Token> = Vec::new();
-            while self.pointer < self.input.len() {
-                if self.skip() {
+        /// Longest non-empty match at the pointer as `(pattern, end)`. Ties are won by the
+        /// higher priority and then by the pattern declared first.
+        /// Searches are anchored at the pointer but see the whole input, so `^` and `\\b` keep their meaning.
+        fn next_match(&self, mode: &$LexerMode) -> Option<(usize, usize)> {
+            let input = Input::new(&self.input).range(self.pointer..).anchored(Anchored::Yes);
+            let mut matched = PatternSet::new(mode.lexer.pattern_len());
+            mode.lexer.which_overlapping_matches(&input, &mut matched);
+            let mut best: Option<(usize, usize)> = None;
+            for pattern in matched.iter() {
+                let index = pattern.as_usize();
+                let end = mode.patterns[index].search(&input).map_or(self.pointer, |m| m.end());
+                if end == self.pointer {
                     continue;
                 }
-                if let Some(token) = self.match_token() {
-                   tokens.push(token);
-                   continue;
+                let better = best.map_or(true, |(best_index, best_end)| {
+                    end > best_end || end == best_end && mode.priorities[index] > mode.priorities[best_index]
+                });
+                if better {
+                    best = Some((index, end));
                 }
+            }
+            best
+        }
+
+        fn run_tokenizing(&mut self) -> Result<Vec<$Token>, $ParseError> {
+            let mut tokens: Vec<$Token> = Vec::new();$init_trivia
+            while self.pointer < self.input.len() {
+                let mode = &MODES[*self.modes.last().unwrap()];
+                if let Some((index, end)) = self.next_match(mode) {
+                    let span = self.advance(end);
+$store_match
+                    match mode.changes[index] {
+                        Some($ModeChange::Push(next)) => self.modes.push(next),
+                        Some($ModeChange::Pop) if self.modes.len() > 1 => {
+                            self.modes.pop();
+                        }
+                        Some($ModeChange::Pop) => {
+                            return Err($ParseError { span, message: \"No tokenizer mode to pop\".to_string() });
+                        }
+                        Some($ModeChange::Switch(next)) => *self.modes.last_mut().unwrap() = next,
+                        None => {}
+                    }
+                    continue;
+                }
+                let end = self.input[self.pointer..].chars().next().map_or(self.pointer, |c| self.pointer + c.len_utf8());
                 return Err(
-                    ParseError {
-                        position: self.pointer,
+                    $ParseError {
+                        span: $Span { start: self.pointer, end, line: self.line, column: self.column },
                         message: \"Expected token\".to_string(),
                     }
                 )
             }
-            tokens.push(Token::Eof);
+            let end = self.input.len();
+            tokens.push($Token::Eof(self.advance(end)$eof_trivia));
             Ok(tokens)
         }
 }
 ";
 
-const TOKENS_ENUM: &'static str = "pub enum Token {";
+const TOKENS_ENUM: &str = "$vis enum $Token {";
 
 pub fn generate_tokenizer(parser_description: &ParserDescription) -> String {
     let mut answer = String::new();
-    let skip = &parser_description.skip;
-    let tokens = &parser_description.tokens;
+    let tokens = get_token_kinds(&parser_description.tokens);
 
 
-    answer.push_str(&get_tokens_enum(tokens));
+    let names = Names::new(&parser_description.options);
 
-    answer.push('\n');
-    answer.push_str(&get_tokens_regex(tokens));
+    answer.push_str(&get_tokens_enum(&tokens, parser_description.options.trivia.is_some(), &names));
 
+    answer.push('\n');
+    answer.push_str(&names.fill(LEXER_MODE));
+    answer.push('\n');
+    answer.push_str(&get_modes(parser_description, &tokens, &names));
     answer.push('\n');
 
-    answer.push_str(&get_skip_regex(skip));
+    answer.push_str(&names.fill(SPAN));
     answer.push('\n');
 
-    answer.push_str(TOKENIZER_STRUCT);
+    answer.push_str(&names.fill(TOKENIZER_STRUCT));
     answer.push('\n');
 
-    answer.push_str(PARSE_ERROR);
+    answer.push_str(&names.fill(PARSE_ERROR));
     answer.push('\n');
 
-    answer.push_str(&get_matching_regex_in_tokenizer(tokens));
+    answer.push_str(&get_token_constructor(&tokens, parser_description.options.trivia.is_some(), &names));
     answer.push('\n');
 
-    answer.push_str(TOKENIZER_BASE_IMPL);
+    answer.push_str(&get_tokenizer_impl(parser_description.options.trivia, &names));
     answer.push('\n');
 
+    if parser_description.options.trivia.is_some() {
+        answer.push_str(&names.fill(TRIVIA));
+        answer.push('\n');
+        answer.push_str(&names.fill(TRIVIA_IMPL));
+        answer.push('\n');
+    }
+
     answer
 }
 
-const TOKENS_ENUM_DERIVES: &'static str = "#[derive(Debug)]";
+const TOKENS_ENUM_DERIVES: &str = "#[derive(Debug)]";
 
-fn get_tokens_enum(tokens: &Vec<Token>) -> String{
+/// First declarations of the tokens of all modes, in declaration order.
+fn get_token_kinds(tokens: &[Token]) -> Vec<&Token> {
+    let mut kinds: Vec<&Token> = Vec::new();
+    for token in tokens {
+        if !kinds.iter().any(|kind| kind.name == token.name) {
+            kinds.push(token);
+        }
+    }
+    kinds
+}
+
+/// Tokens hold their value, the text unless they declare a value type, and their span.
+/// With the `trivia` option they hold the skipped text attached to them too, and
+/// tokens with a value type keep their text at the end for `text()`.
+fn get_tokens_enum(tokens: &[&Token], trivia: bool, names: &Names) -> String{
+    let trivia_field = if trivia { ", Vec<$Trivia>" } else { "" };
     let mut answer = String::new();
     answer.push_str(TOKENS_ENUM_DERIVES);
-    answer.push_str("\n");
-    answer.push_str(TOKENS_ENUM);
+    answer.push('\n');
+    answer.push_str(&names.fill(TOKENS_ENUM));
     answer.push('\n');
     for token in tokens {
-        let name = &token.name;
-        let enum_value = format!("{name}(String),\n");
-        answer.push_str(&enum_value);
+        let enum_value = match &token.payload {
+            Some(payload) if trivia => format!("{}({}, $Span{trivia_field}, String),\n", token.name, payload.ty),
+            Some(payload) => format!("{}({}, $Span),\n", token.name, payload.ty),
+            None => format!("{}(String, $Span{trivia_field}),\n", token.name),
+        };
+        answer.push_str(&names.fill(&enum_value));
     }
 
-    answer.push_str(&format!("{EOF_TOKEN},\n"));
+    answer.push_str(&names.fill(&format!("{EOF_TOKEN}($Span{trivia_field}),\n")));
+    answer.push_str("}\n");
+
+    answer.push_str(&names.fill("impl $Token {\n"));
+    answer.push_str(&get_token_getter("pub fn span(&self) -> $Span", tokens, |_| "_, span, ..", ("span, ..", "*span"), "*span", names));
+    answer.push_str(&get_token_description(tokens, trivia, names));
+    answer.push_str(&get_token_kind(tokens, names));
+    if trivia {
+        let text_fields = |token: &Token| if token.payload.is_some() { ".., text" } else { "text, .." };
+        answer.push_str(&get_token_getter("pub fn text(&self) -> &str", tokens, text_fields, ("..", "\"\""), "text", names));
+        answer.push_str(&get_token_getter("pub fn trivia(&self) -> &[$Trivia]", tokens, |_| "_, _, trivia, ..", ("_, trivia", "trivia"), "trivia", names));
+        answer.push_str(&get_token_getter("fn trivia_mut(&mut self) -> &mut Vec<$Trivia>", tokens, |_| "_, _, trivia, ..", ("_, trivia", "trivia"), "trivia", names));
+    }
     answer.push_str("}\n");
     answer
 }
 
-fn get_tokens_regex(tokens: &Vec<Token>) -> String {
-    let mut answer = String::new();
-    answer.push_str("lazy_static!(\n");
-
+/// `describe` of `$Token` naming the token and quoting its text for parse errors, like ``Num `12` ``.
+/// Tokens with a value type and without their text show the value.
+fn get_token_description(tokens: &[&Token], trivia: bool, names: &Names) -> String {
+    let mut answer = names.fill("fn describe(&self) -> String {\nmatch self {\n");
     for token in tokens {
-        let name = format!("{}_regex", token.name);
-        let regex = format!("Regex::new({})", token.regex);
-        let assignment = format!("static ref {name}: Regex = {regex}.unwrap(); \n");
+        let (fields, text) = match &token.payload {
+            Some(_) if trivia => (".., text", "{text}"),
+            Some(_) => ("value, ..", "{value:?}"),
+            None => ("text, ..", "{text}"),
+        };
+        answer.push_str(&format!("{}::{}({fields}) => format!(\"{} `{text}`\"),\n", names.token, token.name, token.name));
+    }
+    answer.push_str(&format!("{}::{EOF_TOKEN}(..) => \"{EOF_TOKEN}\".to_string(),\n", names.token));
+    answer.push_str("}\n}\n");
+    answer
+}
 
-        answer.push_str(&assignment);
+/// `kind` of `$Token`, its column in the table of the table-driven parser, `Eof` is the last one.
+fn get_token_kind(tokens: &[&Token], names: &Names) -> String {
+    let mut answer = "fn kind(&self) -> usize {\nmatch self {\n".to_string();
+    for (i, token) in tokens.iter().enumerate() {
+        answer.push_str(&format!("{}::{}(..) => {i},\n", names.token, token.name));
     }
+    answer.push_str(&format!("{}::{EOF_TOKEN}(..) => {},\n", names.token, tokens.len()));
+    answer.push_str("}\n}\n");
+    answer
+}
 
-    answer.push_str(");\n");
+/// Method of `$Token` returning `value` bound by the `fields` pattern of every token, `Eof` has a pattern and value of its own.
+fn get_token_getter(
+    signature: &str,
+    tokens: &[&Token],
+    fields: impl Fn(&Token) -> &'static str,
+    eof: (&str, &str),
+    value: &str,
+    names: &Names,
+) -> String {
+    let mut answer = names.fill(&format!("{signature} {{\nmatch self {{\n"));
+    for token in tokens {
+        answer.push_str(&format!("{}::{}({}) => {value},\n", names.token, token.name, fields(token)));
+    }
+    answer.push_str(&format!("{}::{EOF_TOKEN}({}) => {},\n", names.token, eof.0, eof.1));
+    answer.push_str("}\n}\n");
     answer
 }
 
-fn get_skip_regex(skip_regex: &Vec<String>) -> String {
+/// `run_tokenizing` with skipped text thrown away or kept as trivia of the next or the previous token.
+fn get_tokenizer_impl(trivia: Option<TriviaAttachment>, names: &Names) -> String {
+    let store_skipped = match trivia {
+        None => "",
+        Some(TriviaAttachment::Leading) => "None => trivia.push($Trivia { text, span }),",
+        Some(TriviaAttachment::Trailing) => "None => match tokens.last_mut() {
+                            Some(token) => token.trivia_mut().push($Trivia { text, span }),
+                            None => trivia.push($Trivia { text, span }),
+                        },",
+    };
+    let (init_trivia, store_match, eof_trivia) = match trivia {
+        None => (
+            "",
+            "if let Some(token) = mode.tokens[index] {
+                        let text = self.input[span.start .. span.end].to_string();
+                        tokens.push($Tokenizer::token(token, text, span)?);
+                    }".to_string(),
+            "",
+        ),
+        Some(_) => (
+            "\n            let mut trivia: Vec<$Trivia> = Vec::new();",
+            format!("let text = self.input[span.start .. span.end].to_string();
+                    match mode.tokens[index] {{
+                        Some(token) => tokens.push($Tokenizer::token(token, text, span, std::mem::take(&mut trivia))?),
+                        {store_skipped}
+                    }}"),
+            ", trivia",
+        ),
+    };
+    let template = TOKENIZER_BASE_IMPL
+        .replace("$init_trivia", init_trivia)
+        .replace("$store_match", &format!("                    {store_match}"))
+        .replace("$eof_trivia", eof_trivia);
+    names.fill(&template)
+}
+
+/// `MODES` with the default mode first and then the named modes in declaration order.
+fn get_modes(parser_description: &ParserDescription, token_kinds: &[&Token], names: &Names) -> String {
+    let default_mode = TokenMode { name: DEFAULT_MODE.to_string(), skip: parser_description.skip.clone() };
+    let modes: Vec<&TokenMode> = [&default_mode].into_iter().chain(&parser_description.modes).collect();
+    let mode_index = |name: &str| modes.iter().position(|mode| mode.name == name).unwrap();
+
     let mut answer = String::new();
     answer.push_str("lazy_static!(\n");
-    answer.push_str("static ref SKIP_REGEX: Vec<Regex> = vec!(");
-    for regex in skip_regex {
-        let regex_name = format!("Regex::new({regex}).unwrap()");
-        answer.push_str(&regex_name);
-        answer.push(',');
+    answer.push_str(&format!("static ref MODES: Vec<{}> = vec![\n", names.lexer_mode));
+    for mode in &modes {
+        let tokens: Vec<&Token> = parser_description.tokens.iter().filter(|token| token.mode == mode.name).collect();
+        let sources = tokens
+            .iter()
+            .map(|token| &token.regex)
+            .chain(&mode.skip)
+            .map(String::as_str)
+            .collect::<Vec<&str>>()
+            .join(", ");
+        let kinds = tokens
+            .iter()
+            .map(|token| {
+                let kind = token_kinds.iter().position(|kind| kind.name == token.name).unwrap();
+                format!("Some({kind})")
+            })
+            .chain(mode.skip.iter().map(|_| "None".to_string()))
+            .collect::<Vec<String>>()
+            .join(", ");
+        // Skip patterns win ties to keep skipping before tokenizing.
+        let priorities = tokens
+            .iter()
+            .map(|token| token.priority.to_string())
+            .chain(mode.skip.iter().map(|_| "i32::MAX".to_string()))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let changes = tokens
+            .iter()
+            .map(|token| match &token.mode_change {
+                Some(ModeChange::Push(next)) => format!("Some({}::Push({}))", names.mode_change, mode_index(next)),
+                Some(ModeChange::Pop) => format!("Some({}::Pop)", names.mode_change),
+                Some(ModeChange::Switch(next)) => format!("Some({}::Switch({}))", names.mode_change, mode_index(next)),
+                None => "None".to_string(),
+            })
+            .chain(mode.skip.iter().map(|_| "None".to_string()))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        answer.push_str(&format!("// {}\n", mode.name));
+        answer.push_str(&format!("{}::new(&[{sources}], vec![{kinds}], vec![{priorities}], vec![{changes}]),\n", names.lexer_mode));
     }
-
-    answer.push_str(");\n");
+    answer.push_str("];\n");
     answer.push_str(");\n");
     answer
 }
 
-fn get_matching_regex_in_tokenizer(tokens: &Vec<Token>) -> String {
+/// `$Tokenizer::token` making the token of a kind, tokens with a value type convert their text
+/// with `convert` so a failed conversion is a `$ParseError` at the token.
+fn get_token_constructor(tokens: &[&Token], trivia: bool, names: &Names) -> String {
+    let (trivia_arg, trivia_field) = match trivia {
+        true => (", trivia: Vec<$Trivia>", ", trivia"),
+        false => ("", ""),
+    };
     let mut answer = String::new();
 
-    answer.push_str("impl Tokenizer {\n");
-    answer.push_str("fn match_token(&mut self) -> Option<Token> {\n");
-
-    for token in tokens {
-
-        let regex = format!("{}_regex", token.name);
-        let find_validation = format!("if let Some(m) = {regex}.find_at(&self.input, self.pointer) {{");
-        let start_validation = format!("if m.start() == self.pointer {{");
-        let set_pointer = "self.pointer = m.end();";
-        let return_token = format!("return Some(Token::{}(self.input[m.start() .. m.end()].to_string()))", token.name);
-
-        let formatted = format!(
-            "{find_validation}\n{start_validation}\n{set_pointer}\n{return_token}}}}}\n"
-        );
-        answer.push_str(&formatted);
+    answer.push_str(&names.fill("impl $Tokenizer {\n"));
+    answer.push_str(&names.fill(&format!(
+        "fn token(index: usize, text: String, span: $Span{trivia_arg}) -> Result<$Token, $ParseError> {{\n"
+    )));
+    answer.push_str("let token = match index {\n");
+    for (index, token) in tokens.iter().enumerate() {
+        let name = &token.name;
+        let value = match &token.payload {
+            Some(payload) => format!("{}::convert({}, \"{name}\", &text, span)?", names.tokenizer, payload.conversion),
+            None => "text".to_string(),
+        };
+        let text_field = match &token.payload {
+            Some(_) if trivia => ", text",
+            _ => "",
+        };
+        answer.push_str(&format!("{index} => {}::{name}({value}, span{trivia_field}{text_field}),\n", names.token));
     }
-    answer.push_str("None");
-
-    answer.push('\n');
-
+    answer.push_str("_ => unreachable!(\"token index out of range\"),\n");
+    answer.push_str("};\n");
+    answer.push_str("Ok(token)\n");
     answer.push_str("}\n");
+
+    if tokens.iter().any(|token| token.payload.is_some()) {
+        answer.push_str(&names.fill(CONVERT));
+    }
     answer.push_str("}\n");
 
     answer
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_generator::parser_description::parse_parser_description;
+    use crate::parser_generator::names::Names;
+    use super::{get_modes, get_token_kinds};
+
+    #[test]
+    fn modes_have_tokens_skip_patterns_and_changes() {
+        let grammar = "Prelude {} Skip { \" \"; } \
+            Tokens { Ident -> \"[a-z]+\"; If -> \"if\" priority 1; Quote -> \"\\\"\" push(Str); } \
+            Tokens mode Str { Text -> \"[^\\\"]+\"; Quote -> \"\\\"\" pop; } \
+            NotTerminals { S {} {} -> Quote Text Quote; }";
+        let description = parse_parser_description(grammar).unwrap();
+        let token_kinds = get_token_kinds(&description.tokens);
+        assert_eq!(vec!["Ident", "If", "Quote", "Text"], token_kinds.iter().map(|t| t.name.as_str()).collect::<Vec<_>>());
+
+        let modes = get_modes(&description, &token_kinds, &Names::new(&description.options));
+        assert!(modes.contains(r#"LexerMode::new(&["[a-z]+", "if", "\"", " "], vec![Some(0), Some(1), Some(2), None], vec![0, 1, 0, i32::MAX], vec![None, None, Some(ModeChange::Push(1)), None]),"#));
+        assert!(modes.contains(r#"LexerMode::new(&["[^\"]+", "\""], vec![Some(3), Some(2)], vec![0, 0], vec![None, Some(ModeChange::Pop)]),"#));
+
+        let prefixed = grammar.replace("Prelude {}", "Options { prefix = \"Str\"; } Prelude {}");
+        let description = parse_parser_description(&prefixed).unwrap();
+        let modes = get_modes(&description, &token_kinds, &Names::new(&description.options));
+        assert!(modes.contains("static ref MODES: Vec<StrLexerMode>"));
+        assert!(modes.contains(r#"StrLexerMode::new(&["[^\"]+", "\""], vec![Some(3), Some(2)], vec![0, 0], vec![None, Some(StrModeChange::Pop)]),"#));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::parser_generator::names::Names;
+use crate::parser_generator::parser_description::{ParseTree, ParserDescription};
+
+const TREE_BUILDER: &str =
+    "
+/// Builds the parse tree: the parser calls `leaf` for every token it reads and `node` for every
+/// not terminal once its children are built. `id` numbers the nodes in the order they are started.
+$vis trait $TreeBuilder {
+    type Node;
+
+    /// Node of a token, or the `error` node of a not terminal recovered from.
+    fn leaf(&mut self, id: usize, label: &str, span: $Span) -> Self::Node;
+
+    fn node(&mut self, id: usize, label: &str, span: $Span, children: Vec<Self::Node>) -> Self::Node;
+}
+
+impl<B: $TreeBuilder + ?Sized> $TreeBuilder for &mut B {
+    type Node = B::Node;
+
+    fn leaf(&mut self, id: usize, label: &str, span: $Span) -> Self::Node {
+        (**self).leaf(id, label, span)
+    }
+
+    fn node(&mut self, id: usize, label: &str, span: $Span, children: Vec<Self::Node>) -> Self::Node {
+        (**self).node(id, label, span, children)
+    }
+}
+
+/// Builds no tree, for parsing only the returns.
+#[derive(Debug, Clone, Copy, Default)]
+$vis struct $NoTree;
+
+impl $TreeBuilder for $NoTree {
+    type Node = ();
+
+    fn leaf(&mut self, id: usize, label: &str, span: $Span) {}
+
+    fn node(&mut self, id: usize, label: &str, span: $Span, children: Vec<()>) {}
+}
+";
+
+const GRAPH_VIZ_BUILDER: &str =
+    "
+/// Builds the `GraphVizNode` tree of the `graph_viz` crate.
+#[derive(Debug, Clone, Copy, Default)]
+$vis struct $GraphVizBuilder;
+
+impl $TreeBuilder for $GraphVizBuilder {
+    type Node = GraphVizNode;
+
+    fn leaf(&mut self, id: usize, label: &str, span: $Span) -> GraphVizNode {
+        GraphVizNode::new_leaf(id.to_string(), label.to_string())
+    }
+
+    fn node(&mut self, id: usize, label: &str, span: $Span, children: Vec<GraphVizNode>) -> GraphVizNode {
+        GraphVizNode::new_node(id.to_string(), label.to_string(), children)
+    }
+}
+";
+
+/// `$TreeBuilder` with the builder of no tree, and the `GraphVizNode` builder unless the grammar opts out of the tree.
+pub fn generate_tree_builders(parser_description: &ParserDescription) -> String {
+    let names = Names::new(&parser_description.options);
+    let mut answer = names.fill(TREE_BUILDER);
+    if parser_description.options.tree == ParseTree::GraphViz {
+        answer.push_str(&names.fill(GRAPH_VIZ_BUILDER));
+    }
+    answer
+}
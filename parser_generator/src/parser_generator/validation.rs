@@ -0,0 +1,411 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::parser_generator::action_code::{identifier_positions, readable_results};
+use crate::parser_generator::labels::label_references;
+use crate::parser_generator::parser_description::{GrammarError, ParseTree, ParserDescription, Rule, RuleMember, RuleToken, Typed};
+use crate::parser_generator::parser_generator::S;
+
+lazy_static! {
+    static ref POSITIONAL_REGEX: Regex = Regex::new("^([A-Za-z]+)([0-9]+)_([A-Za-z0-9_]+)$").unwrap();
+}
+
+/// All errors found by [`validate`], ordered by their position in the grammar.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ValidationReport {
+    pub errors: Vec<GrammarError>,
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Invalid grammar, {} error(s) found:", self.errors.len())?;
+        for error in &self.errors {
+            write!(f, "{error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationReport {}
+
+/// Checks the names a grammar refers to before any rewrite: every rule member is a declared
+/// token or not terminal, names are declared once, not terminals don't take names of tokens and `S` is one of them,
+/// calls pass as many arguments as the not terminal takes, `E1_res` and `label.res` in
+/// actions and call arguments are results of a member of the alternative, and with the `ast`
+/// tree an alternative promotes at most one member.
+pub fn validate(parser_description: &ParserDescription, source: &str) -> Result<(), ValidationReport> {
+    let mut validator = Validator {
+        source,
+        results: readable_results(parser_description),
+        args: HashMap::new(),
+        tree: parser_description.options.tree,
+        errors: Vec::new(),
+    };
+    validator.check_declarations(parser_description);
+
+    for not_term in &parser_description.not_terminal {
+        validator.args.entry(not_term.name.clone()).or_insert(not_term.args.len());
+    }
+
+    for not_term in &parser_description.not_terminal {
+        let mut cursor = not_term.position;
+        for rule in &not_term.rules {
+            cursor = validator.check_rule(rule, &Scope::default(), cursor);
+        }
+    }
+
+    let mut errors = validator.errors;
+    errors.sort_by_key(|(position, _)| *position);
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(ValidationReport { errors: errors.into_iter().map(|(_, error)| error).collect() }),
+    }
+}
+
+/// Members an action can refer to, by position as `(name, index)` and by label.
+#[derive(Clone, Default)]
+struct Scope {
+    members: HashSet<(String, usize)>,
+    labels: HashMap<String, String>,
+}
+
+struct Validator<'a> {
+    source: &'a str,
+    /// Results of tokens and not terminals, see [`member_results`].
+    results: HashMap<String, Vec<Typed>>,
+    /// Number of arguments of every not terminal.
+    args: HashMap<String, usize>,
+    tree: ParseTree,
+    errors: Vec<(usize, GrammarError)>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, position: usize, section: &str, expected: String) {
+        self.errors.push((position, GrammarError::new(self.source, position, Some(section), expected)));
+    }
+
+    fn check_declarations(&mut self, parser_description: &ParserDescription) {
+        let mut tokens = HashSet::new();
+        for token in &parser_description.tokens {
+            if !tokens.insert((&token.name, &token.mode)) {
+                self.error(token.position, "Tokens", format!("new token name instead of `{}`", token.name));
+            }
+        }
+
+        let mut not_terminals = HashSet::new();
+        for not_term in &parser_description.not_terminal {
+            let name = &not_term.name;
+            if parser_description.tokens.iter().any(|token| &token.name == name) {
+                self.error(not_term.position, "NotTerminals", format!("not terminal name other than token `{name}`"));
+            } else if !not_terminals.insert(name) {
+                self.error(not_term.position, "NotTerminals", format!("new not terminal name instead of `{name}`"));
+            }
+        }
+
+        if !parser_description.not_terminal.iter().any(|not_term| not_term.name == S) {
+            let section = parser_description.not_terminal.first().map_or(self.source.len(), |not_term| not_term.position);
+            let position = self.source[..section].rfind("NotTerminals").unwrap_or(0);
+            self.error(position, "NotTerminals", format!("start not terminal `{S}`"));
+        }
+    }
+
+    /// Checks the members of the alternative, `cursor` is an offset in the grammar before
+    /// the alternative and the returned one is after it, actions are looked for between them.
+    fn check_rule(&mut self, rule: &Rule, outer: &Scope, mut cursor: usize) -> usize {
+        let mut scope = outer.clone();
+        let mut labels = HashSet::new();
+        for (i, member) in rule.members.iter().enumerate() {
+            if let RuleMember::RuleCall(RuleToken { name, annotation, position, .. }) = member {
+                scope.members.insert((name.clone(), i));
+                if let Some(label) = &annotation.label {
+                    if !labels.insert(label) {
+                        let position = self.source[..*position].rfind(label.as_str()).unwrap_or(*position);
+                        self.error(position, "NotTerminals", format!("new label instead of `{label}`"));
+                    }
+                    scope.labels.insert(label.clone(), name.clone());
+                }
+            }
+        }
+
+        let mut promoted = false;
+        for member in &rule.members {
+            let annotation = match member {
+                RuleMember::RuleCall(token) => {
+                    cursor = self.check_call(token, &scope);
+                    &token.annotation
+                }
+                RuleMember::Command(code) => {
+                    let position = self.find(code, cursor);
+                    self.check_references(code, position, &scope);
+                    cursor = position + code.len();
+                    continue;
+                }
+                RuleMember::Group(group) => {
+                    for alternative in &group.alternatives {
+                        cursor = self.check_rule(alternative, &scope, cursor);
+                    }
+                    cursor = self.skip(self.find(")", cursor) + 1, &['*', '+', '?']);
+                    &group.annotation
+                }
+            };
+            let suffix = cursor;
+            cursor = self.skip(cursor, &['^', '!']);
+            if annotation.promote && self.tree == ParseTree::Ast {
+                if promoted {
+                    let position = self.find("^", suffix);
+                    self.error(position, "NotTerminals", "one promoted `^` member per alternative".to_string());
+                }
+                promoted = true;
+            }
+        }
+        cursor
+    }
+
+    fn check_call(&mut self, token: &RuleToken, scope: &Scope) -> usize {
+        let RuleToken { name, args, position, .. } = token;
+        let mut cursor = position + name.len();
+        if !self.results.contains_key(name) {
+            self.error(*position, "NotTerminals", format!("declared token or not terminal instead of `{name}`"));
+            return cursor;
+        }
+        if args != "()" {
+            let args_position = self.find(args, cursor);
+            self.check_references(args, args_position, scope);
+            cursor = args_position + args.len();
+        }
+
+        let expected = self.args.get(name).copied().unwrap_or(0);
+        let found = count_args(args);
+        if expected != found {
+            self.error(*position, "NotTerminals", format!("{expected} argument(s) of `{name}` instead of {found}"));
+        }
+        cursor
+    }
+
+    /// Checks `E1_res` and `label.res` references in the code found at `position` in the grammar.
+    fn check_references(&mut self, code: &str, position: usize, scope: &Scope) {
+        for (offset, ident) in identifier_positions(code) {
+            let Some(caps) = POSITIONAL_REGEX.captures(ident) else {
+                continue;
+            };
+            let (name, result) = (&caps[1], &caps[3]);
+            if !self.results.contains_key(name) {
+                continue;
+            }
+            let index: usize = caps[2].parse().unwrap_or(usize::MAX);
+            if !scope.members.contains(&(name.to_string(), index)) {
+                let expected = format!("member `{name}` at index {index} of the alternative for `{ident}`");
+                self.error(position + offset, "NotTerminals", expected);
+            } else {
+                self.check_result(name, result, ident, position + offset);
+            }
+        }
+
+        for (offset, label, result) in label_references(code) {
+            if let Some(name) = scope.labels.get(label).cloned() {
+                let reference = format!("{label}.{result}");
+                self.check_result(&name, result, &reference, position + offset);
+            }
+        }
+    }
+
+    fn check_result(&mut self, name: &str, result: &str, reference: &str, position: usize) {
+        let results = &self.results[name];
+        if results.iter().any(|typed| typed.name == result) {
+            return;
+        }
+        let names = results.iter().map(|typed| format!("`{}`", typed.name)).collect::<Vec<String>>().join(", ");
+        self.error(position, "NotTerminals", format!("result of `{name}` ({names}) instead of `{reference}`"));
+    }
+
+    /// Offset after the one of `chars` following `cursor` after whitespace, or `cursor` if there is none.
+    fn skip(&self, cursor: usize, chars: &[char]) -> usize {
+        let rest = &self.source[cursor..];
+        let trimmed = rest.trim_start();
+        match trimmed.starts_with(chars) {
+            true => cursor + rest.len() - trimmed.len() + 1,
+            false => cursor,
+        }
+    }
+
+    /// Offset of `text` at or after `cursor` in the grammar, rules keep actions and arguments as written.
+    fn find(&self, text: &str, cursor: usize) -> usize {
+        self.source[cursor..].find(text).map_or(cursor, |offset| cursor + offset)
+    }
+}
+
+/// Arguments of `(a, f(b, c), d)` separated by commas outside of brackets, strings, char literals,
+/// closure parameters like `|a, b|` and generic arguments of a turbofish like `::<K, V>`.
+fn count_args(args: &str) -> usize {
+    let inner = args.trim().strip_prefix('(').and_then(|a| a.strip_suffix(')')).unwrap_or(args);
+    let chars: Vec<char> = inner.chars().collect();
+    let mut count = 0;
+    let mut depth = 0usize;
+    let mut generics = 0usize;
+    let mut in_string = false;
+    let mut in_params = false;
+    let mut escaped = false;
+    let mut empty = true;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        match c {
+            _ if in_string => {
+                in_string = c != '"' || escaped;
+                escaped = c == '\\' && !escaped;
+            }
+            '"' => in_string = true,
+            '\'' => i = char_literal_end(&chars, i),
+            '|' if in_params => in_params = false,
+            '|' if starts_expression(&chars[..i - 1]) => in_params = true,
+            '<' if generics > 0 || chars[..i - 1].ends_with(&[':', ':']) => generics += 1,
+            '>' if generics > 0 => generics -= 1,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 && generics == 0 && !in_params => {
+                count += usize::from(!empty);
+                empty = true;
+                continue;
+            }
+            _ => {}
+        }
+        empty &= c.is_whitespace();
+    }
+    count + usize::from(!empty)
+}
+
+/// Index after the char literal whose opening quote is before `start`, or `start` for a lifetime like `'a`.
+fn char_literal_end(chars: &[char], start: usize) -> usize {
+    match chars.get(start..) {
+        Some(['\\', rest @ ..]) => rest.iter().position(|c| *c == '\'').map_or(start, |end| start + end + 2),
+        Some([_, '\'', ..]) => start + 2,
+        _ => start,
+    }
+}
+
+/// Whether an expression starts after `before`, so a `|` there opens the parameters of a closure.
+fn starts_expression(before: &[char]) -> bool {
+    let before: String = before.iter().collect();
+    let before = before.trim_end();
+    before.is_empty() || before.ends_with(['(', '[', '{', ',', '=', ';']) || before.ends_with("move")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_generator::parser_description::parse_parser_description;
+    use super::{count_args, validate};
+
+    const CALCULATOR: &str = include_str!("../../resources/calculator.txt");
+
+    fn errors(grammar: &str) -> Vec<(usize, usize, String)> {
+        let description = parse_parser_description(grammar).unwrap();
+        match validate(&description, grammar) {
+            Ok(()) => Vec::new(),
+            Err(report) => report.errors.into_iter().map(|err| (err.line, err.column, err.expected)).collect(),
+        }
+    }
+
+    #[test]
+    fn calculator_is_valid() {
+        assert_eq!(Vec::<(usize, usize, String)>::new(), errors(CALCULATOR));
+    }
+
+    #[test]
+    fn undefined_symbols() {
+        let grammar = CALCULATOR.replace("| Num {res = Num0_ident", "| Nm {res = Num0_ident");
+        assert_eq!(
+            vec![
+                (61, 51, "declared token or not terminal instead of `Nm`".to_string()),
+                (61, 61, "member `Num` at index 0 of the alternative for `Num0_ident`".to_string()),
+            ],
+            errors(&grammar),
+        );
+    }
+
+    #[test]
+    fn duplicate_and_shadowing_names() {
+        let grammar = CALCULATOR
+            .replace("Div -> \"/\";", "Div -> \"/\"; Mul -> \"x\";")
+            .replace("    S {} {res # i64}", "    Comb {} {} -> Plus; E {} {} -> Minus;\n    S {} {res # i64}")
+            .replace("Minus T Ex(acc - T1_res)", "Minus t:T t:Ex(acc - T1_res)");
+        assert_eq!(
+            vec![
+                (33, 17, "new token name instead of `Mul`".to_string()),
+                (41, 5, "not terminal name other than token `Comb`".to_string()),
+                (48, 5, "new not terminal name instead of `E`".to_string()),
+                (51, 45, "new label instead of `t`".to_string()),
+            ],
+            errors(&grammar),
+        );
+    }
+
+    #[test]
+    fn missing_start_not_terminal() {
+        let grammar = CALCULATOR.replace("    S {} {res # i64} -> C {res = C0_res;};\n", "");
+        assert_eq!(vec![(39, 1, "start not terminal `S`".to_string())], errors(&grammar));
+        let empty = "Prelude {} Skip {} Tokens { Num -> \"[0-9]+\"; } NotTerminals { }";
+        assert_eq!(vec![(1, 48, "start not terminal `S`".to_string())], errors(empty));
+    }
+
+    #[test]
+    fn argument_counts() {
+        let grammar = CALCULATOR.replace("Plus T Ex(acc + T1_res)", "Plus T Ex(acc, T1_res)").replace("E Cx (E0_res)", "E Cx");
+        assert_eq!(
+            vec![
+                (43, 27, "1 argument(s) of `Cx` instead of 0".to_string()),
+                (49, 42, "1 argument(s) of `Ex` instead of 2".to_string()),
+            ],
+            errors(&grammar),
+        );
+        assert_eq!((0, 1, 3, 2), (count_args("()"), count_args("(a.max(b, c))"), count_args("(\"a,\", [b, c], d,)"), count_args("(x, y)")));
+        assert_eq!((1, 2, 1), (count_args("(',')"), count_args("('\\'', '}')"), count_args("(|a: i64, b: i64| a + b)")));
+        assert_eq!((1, 2), (count_args("(HashMap::<i64, i64>::new().len())"), count_args("(a < b, c > d)")));
+        let grammar = CALCULATOR.replace(
+            "Plus T Ex(acc + T1_res)",
+            "Plus T Ex((|a: i64, b: i64| a + b)(acc, T1_res) + HashMap::<i64, i64>::new().len() as i64 - (',' as i64 - 44))",
+        );
+        assert_eq!(Vec::<(usize, usize, String)>::new(), errors(&grammar));
+    }
+
+    #[test]
+    fn unknown_results() {
+        let grammar = CALCULATOR
+            .replace("{res = E1_res;}", "{res = E1_result + E0_res;}")
+            .replace("Minus F {res = - F1_res;}", "Minus f:F {res = - f.value + f.res; let _ = f.span;}");
+        assert_eq!(
+            vec![
+                (59, 44, "result of `F` (`res`, `span`) instead of `f.value`".to_string()),
+                (61, 40, "result of `E` (`res`, `span`) instead of `E1_result`".to_string()),
+                (61, 52, "member `E` at index 0 of the alternative for `E0_res`".to_string()),
+            ],
+            errors(&grammar),
+        );
+    }
+
+    #[test]
+    fn one_promoted_member_per_alternative() {
+        let grammar = format!(
+            "Options {{ tree = \"ast\"; }}\n{}",
+            CALCULATOR.replace("-> Lb E Rb {res = E1_res;}", "-> Lb^ E^ Rb {res = E1_res;} (Plus Num^ | Minus^)^"),
+        );
+        assert_eq!(
+            vec![(62, 30, "one promoted `^` member per alternative".to_string()), (62, 71, "one promoted `^` member per alternative".to_string())],
+            errors(&grammar),
+        );
+        assert_eq!(Vec::<(usize, usize, String)>::new(), errors(&CALCULATOR.replace("-> Lb E Rb {res = E1_res;}", "-> Lb^ E^ Rb {res = E1_res;}")));
+    }
+
+    #[test]
+    fn group_members_see_outer_members() {
+        let grammar = CALCULATOR.replace(
+            "S {} {res # i64} -> C {res = C0_res;};",
+            "S {} {res # i64} -> first:C {res = C0_res;} (Plus C {res += first.res + C1_res + C0_rest;})*;",
+        );
+        assert_eq!(vec![(41, 86, "result of `C` (`res`, `span`) instead of `C0_rest`".to_string())], errors(&grammar));
+    }
+}